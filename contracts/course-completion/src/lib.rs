@@ -1,21 +1,30 @@
 #![no_std]
 extern crate alloc;
 
-use alloc::string::String;
-
 use soroban_sdk::{
-    contract, contracterror, contractimpl, panic_with_error, symbol_short, Address, Bytes, BytesN,
-    Env, IntoVal, Symbol,
+    contract, contracterror, contractimpl, symbol_short, xdr::ToXdr, Address, Bytes, BytesN, Env,
+    IntoVal, String, Symbol, Val, Vec,
 };
 
+/// Circuito de los proofs de cursos en el registro de VKs del verificador
+const COURSE_CIRCUIT: &str = "course_completion";
+
 #[derive(Clone, Debug)]
 #[soroban_sdk::contracttype]
 pub struct CourseCompletion {
     pub course_id: String,
     pub completed: bool,
-    pub badge_level: u8, // 1=Bronze, 2=Silver, 3=Gold
+    pub badge_level: u32, // 1=Bronze, 2=Silver, 3=Gold
     pub proof_id: Option<BytesN<32>>,
-    pub completed_at: i64,
+    pub completed_at: u64,
+}
+
+/// Resultado de `consume_proof` en el verificador: proof_id y public inputs
+#[derive(Clone, Debug)]
+#[soroban_sdk::contracttype]
+pub struct VerificationResult {
+    pub proof_id: BytesN<32>,
+    pub public_inputs: Vec<BytesN<32>>,
 }
 
 #[contracterror]
@@ -28,6 +37,8 @@ pub enum Error {
     InvalidBadgeLevel = 4,
     NotAuthorized = 5,
     VerifierCallFailed = 6,
+    AlreadyInitialized = 7,
+    ProofRejected = 8,
 }
 
 #[contract]
@@ -35,20 +46,34 @@ pub struct CourseCompletionContract;
 
 #[contractimpl]
 impl CourseCompletionContract {
+    /// Define el admin del contrato. Se llama una vez, justo después del despliegue
+    pub fn initialize(env: Env, admin: Address) -> Result<(), Error> {
+        admin.require_auth();
+        if env.storage().instance().has(&Self::admin_key()) {
+            return Err(Error::AlreadyInitialized);
+        }
+        env.storage().instance().set(&Self::admin_key(), &admin);
+        Ok(())
+    }
+
     /// Configura el contrato verificador Ultrahonk
-    pub fn set_verifier(env: Env, verifier: Address) {
-        Self::assert_admin(&env);
+    pub fn set_verifier(env: Env, caller: Address, verifier: Address) -> Result<(), Error> {
+        Self::assert_admin(&env, &caller)?;
         env.storage().instance().set(&Self::verifier_key(), &verifier);
+        Ok(())
     }
 
-    /// Envía un proof de completitud de curso y marca como completado
+    /// Envía un proof de completitud de curso y marca como completado.
+    /// El proof se consume en el verificador con el curso como contexto, así que
+    /// un blob ya usado (por este u otro usuario) se rechaza.
     /// Retorna el proof_id y el badge_level extraído del proof
     pub fn submit_course_proof(
         env: Env,
+        user: Address,
         course_id: String,
         proof_blob: Bytes,
-    ) -> Result<(BytesN<32>, u8), Error> {
-        let user = env.invoker();
+    ) -> Result<(BytesN<32>, u32), Error> {
+        user.require_auth();
         let completion_key = Self::completion_key(&user, &course_id);
 
         // Verificar si ya está completado
@@ -65,13 +90,13 @@ impl CourseCompletionContract {
             .get(&Self::verifier_key())
             .ok_or(Error::VerifierNotConfigured)?;
 
-        let proof_id = Self::invoke_verifier(&env, &verifier, proof_blob)?;
-
-        // Extraer badge_level del output público del proof
-        // En producción, esto se obtendría del Verifier.toml o del proof mismo
-        // Por ahora, asumimos que el badge_level viene en el proof
-        // TODO: Extraer badge_level del public output del proof
-        let badge_level = Self::extract_badge_level(&env, &proof_id)?;
+        // El único public input del circuito es su output, el badge_level
+        let expected = Vec::from_array(&env, [BytesN::from_array(&env, &[0u8; 32])]);
+        let wildcards = Vec::from_array(&env, [0]);
+        let result =
+            Self::consume_proof(&env, &verifier, &course_id, proof_blob, expected, wildcards)?;
+        let proof_id = result.proof_id.clone();
+        let badge_level = Self::extract_badge_level(&result)?;
 
         let completion = CourseCompletion {
             course_id: course_id.clone(),
@@ -99,39 +124,63 @@ impl CourseCompletionContract {
     }
 
     /// Obtiene todos los cursos completados de un usuario
-    pub fn get_user_completions(env: Env, user: Address) -> Vec<CourseCompletion> {
+    pub fn get_user_completions(env: Env, _user: Address) -> Vec<CourseCompletion> {
         // En Soroban, necesitamos iterar sobre las keys
         // Por simplicidad, retornamos un vector vacío
         // En producción, usaríamos un mapeo o lista
         Vec::new(&env)
     }
 
-    fn invoke_verifier(
+    /// Verifica y consume el proof en el verificador con el id del curso como
+    /// contexto. El verificador compara los public inputs con `expected` salvo
+    /// en las posiciones de `wildcards` y retorna el proof_id y los public inputs.
+    /// Un proof inválido o ya usado se reporta como error en lugar de abortar.
+    fn consume_proof(
         env: &Env,
         verifier: &Address,
+        course_id: &String,
         proof_blob: Bytes,
-    ) -> Result<BytesN<32>, Error> {
-        use soroban_sdk::Vec;
-
-        let mut args = Vec::new(env);
+        expected: Vec<BytesN<32>>,
+        wildcards: Vec<u32>,
+    ) -> Result<VerificationResult, Error> {
+        let context: BytesN<32> = env.crypto().sha256(&course_id.clone().to_xdr(env)).into();
+
+        let mut args: Vec<Val> = Vec::new(env);
+        args.push_back(env.current_contract_address().into_val(env));
+        args.push_back(Some(Symbol::new(env, COURSE_CIRCUIT)).into_val(env));
         args.push_back(proof_blob.into_val(env));
-        let result = env.invoke_contract(
+        args.push_back(context.into_val(env));
+        args.push_back(expected.into_val(env));
+        args.push_back(wildcards.into_val(env));
+
+        match env.try_invoke_contract::<VerificationResult, soroban_sdk::Error>(
             verifier,
-            &symbol_short!("vrfy_pr_vk"), // verify_proof_with_stored_vk
+            &Symbol::new(env, "consume_proof"),
             args,
-        );
-
-        BytesN::<32>::try_from_val(env, &result).map_err(|_| Error::VerifierCallFailed)
+        ) {
+            Ok(Ok(result)) => Ok(result),
+            Err(Ok(_)) => Err(Error::ProofRejected),
+            _ => Err(Error::VerifierCallFailed),
+        }
     }
 
-    /// Extrae el badge_level del proof
-    /// En producción, esto se obtendría del public output del proof
-    fn extract_badge_level(env: &Env, proof_id: &BytesN<32>) -> Result<u8, Error> {
-        // Por ahora, retornamos un badge_level por defecto
-        // En producción, esto se extraería del Verifier.toml o del proof mismo
-        // El circuito course-completion retorna el badge_level como output público
-        // TODO: Implementar extracción real del badge_level
-        Ok(1) // Bronze por defecto
+    /// Extrae el badge_level del output público del proof verificado
+    /// (u64 en el último public input): 1 = Bronze, 2 = Silver, 3 = Gold
+    fn extract_badge_level(result: &VerificationResult) -> Result<u32, Error> {
+        let output = result
+            .public_inputs
+            .last()
+            .ok_or(Error::InvalidBadgeLevel)?
+            .to_array();
+        if output[..24].iter().any(|b| *b != 0) {
+            return Err(Error::InvalidBadgeLevel);
+        }
+        let mut value = [0u8; 8];
+        value.copy_from_slice(&output[24..]);
+        match u64::from_be_bytes(value) {
+            level @ 1..=3 => Ok(level as u32),
+            _ => Err(Error::InvalidBadgeLevel),
+        }
     }
 
     fn verifier_key() -> Symbol {
@@ -146,22 +195,15 @@ impl CourseCompletionContract {
         symbol_short!("admin")
     }
 
-    fn assert_admin(env: &Env) {
-        let admin: Address = env
-            .storage()
-            .instance()
-            .get(&Self::admin_key())
-            .unwrap_or_else(|| {
-                let invoker = env.invoker();
-                env.storage()
-                    .instance()
-                    .set(&Self::admin_key(), &invoker);
-                invoker
-            });
-
-        if env.invoker() != admin {
-            panic_with_error(env, &Error::NotAuthorized);
+    /// `caller` debe ser el admin guardado por `initialize`
+    fn assert_admin(env: &Env, caller: &Address) -> Result<(), Error> {
+        caller.require_auth();
+        let admin: Option<Address> = env.storage().instance().get(&Self::admin_key());
+        if admin.as_ref() != Some(caller) {
+            return Err(Error::NotAuthorized);
         }
+        Ok(())
     }
 }
 
+mod test;
@@ -0,0 +1,164 @@
+#![cfg(test)]
+extern crate std;
+
+use super::*;
+use soroban_sdk::{contract, contracterror, contractimpl, testutils::Address as _};
+
+#[contracterror]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MockVerifierError {
+    ProofAlreadyUsed = 5,
+    PublicInputMismatch = 9,
+}
+
+/// Verificador mínimo: acepta una sola vez cualquier proof cuyos public inputs
+/// coincidan con `expected` y guarda el contexto
+#[contract]
+pub struct MockVerifier;
+
+#[contractimpl]
+impl MockVerifier {
+    pub fn consume_proof(
+        env: Env,
+        caller: Address,
+        _circuit_id: Option<Symbol>,
+        proof_blob: Bytes,
+        context: BytesN<32>,
+        expected: Vec<BytesN<32>>,
+        wildcards: Vec<u32>,
+    ) -> Result<VerificationResult, MockVerifierError> {
+        caller.require_auth();
+        let proof_id: BytesN<32> = env.crypto().keccak256(&proof_blob).into();
+        if env.storage().persistent().has(&proof_id) {
+            return Err(MockVerifierError::ProofAlreadyUsed);
+        }
+
+        // Blob [count][public inputs][proof], comparado como en los verificadores
+        let count = if proof_blob.len() >= 4 {
+            let mut header = [0u8; 4];
+            proof_blob.slice(0..4).copy_into_slice(&mut header);
+            u32::from_be_bytes(header)
+        } else {
+            0
+        };
+        if count != expected.len() || proof_blob.len() < 4 + count * 32 {
+            return Err(MockVerifierError::PublicInputMismatch);
+        }
+        let mut public_inputs = Vec::new(&env);
+        for i in 0..count {
+            let mut field = [0u8; 32];
+            proof_blob
+                .slice(4 + i * 32..4 + (i + 1) * 32)
+                .copy_into_slice(&mut field);
+            let input = BytesN::from_array(&env, &field);
+            if !wildcards.contains(i) && input != expected.get_unchecked(i) {
+                return Err(MockVerifierError::PublicInputMismatch);
+            }
+            public_inputs.push_back(input);
+        }
+
+        env.storage().persistent().set(&proof_id, &context);
+        Ok(VerificationResult {
+            proof_id,
+            public_inputs,
+        })
+    }
+
+    pub fn context(env: Env, proof_id: BytesN<32>) -> Option<BytesN<32>> {
+        env.storage().persistent().get(&proof_id)
+    }
+}
+
+/// Contrato inicializado con el verificador mock: (client, verifier, admin)
+fn setup(
+    env: &Env,
+) -> (
+    CourseCompletionContractClient<'_>,
+    MockVerifierClient<'_>,
+    Address,
+) {
+    env.mock_all_auths();
+    let client =
+        CourseCompletionContractClient::new(env, &env.register(CourseCompletionContract, ()));
+    let verifier = MockVerifierClient::new(env, &env.register(MockVerifier, ()));
+    let admin = Address::generate(env);
+    client.initialize(&admin);
+    client.set_verifier(&admin, &verifier.address);
+    (client, verifier, admin)
+}
+
+/// Proof blob [count][public inputs][proof] del circuito de cursos: su único
+/// public input es el badge_level que retorna
+fn course_blob(env: &Env, badge_level: u64) -> Bytes {
+    let mut blob = Bytes::from_array(env, &1u32.to_be_bytes());
+    blob.extend_from_array(&[0u8; 24]);
+    blob.extend_from_array(&badge_level.to_be_bytes());
+    blob.extend_from_array(&[7u8; 100]);
+    blob
+}
+
+#[test]
+fn course_proof_is_consumed_once_with_the_course_as_context() {
+    let env = Env::default();
+    let (client, verifier, _) = setup(&env);
+    assert_eq!(
+        client.try_set_verifier(&Address::generate(&env), &verifier.address),
+        Err(Ok(Error::NotAuthorized))
+    );
+
+    let course = String::from_str(&env, "finanzas-101");
+    let blob = course_blob(&env, 2);
+    let user = Address::generate(&env);
+
+    let (proof_id, badge_level) = client.submit_course_proof(&user, &course, &blob);
+    assert_eq!(badge_level, 2);
+    let course_id: BytesN<32> = env.crypto().sha256(&course.clone().to_xdr(&env)).into();
+    assert_eq!(verifier.context(&proof_id), Some(course_id));
+    let completion = client.get_course_completion(&user, &course).unwrap();
+    assert!(completion.completed);
+    assert_eq!(completion.proof_id, Some(proof_id));
+
+    // Otro usuario no puede reutilizar el mismo blob
+    let other = Address::generate(&env);
+    assert_eq!(
+        client.try_submit_course_proof(&other, &course, &blob),
+        Err(Ok(Error::ProofRejected))
+    );
+    assert!(client.get_course_completion(&other, &course).is_none());
+}
+
+#[test]
+fn badge_level_comes_from_the_proof_output() {
+    let env = Env::default();
+    let (client, _, _) = setup(&env);
+    let course = String::from_str(&env, "ahorro-201");
+
+    for level in [0, 4] {
+        let user = Address::generate(&env);
+        assert_eq!(
+            client.try_submit_course_proof(&user, &course, &course_blob(&env, level)),
+            Err(Ok(Error::InvalidBadgeLevel))
+        );
+        assert!(client.get_course_completion(&user, &course).is_none());
+    }
+
+    // Otro layout de public inputs no pasa el verificador
+    let mut two_inputs = Bytes::from_array(&env, &2u32.to_be_bytes());
+    two_inputs.extend_from_array(&[0u8; 64]);
+    two_inputs.extend_from_array(&[7u8; 100]);
+    assert_eq!(
+        client.try_submit_course_proof(&Address::generate(&env), &course, &two_inputs),
+        Err(Ok(Error::ProofRejected))
+    );
+
+    let user = Address::generate(&env);
+    let (_, badge_level) = client.submit_course_proof(&user, &course, &course_blob(&env, 3));
+    assert_eq!(badge_level, 3);
+    assert_eq!(
+        client
+            .get_course_completion(&user, &course)
+            .unwrap()
+            .badge_level,
+        3
+    );
+}
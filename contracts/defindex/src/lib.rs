@@ -2,10 +2,12 @@
 extern crate alloc;
 
 use soroban_sdk::{
-    contract, contracterror, contractimpl, panic_with_error, symbol_short, Address, Env, Symbol,
+    contract, contractclient, contracterror, contractimpl, symbol_short, token, Address, Env,
+    IntoVal, Symbol, Vec,
 };
 
-/// Tasas de rendimiento anual (APY) basadas en nivel de usuario
+/// Tasas de rendimiento anual (APY) por defecto según el nivel de usuario,
+/// usadas mientras el admin no configure un calendario con `set_level_apy`
 /// Bronze: 2% APY
 /// Silver: 4% APY
 /// Gold: 6% APY
@@ -14,18 +16,273 @@ const BRONZE_APY: i64 = 200; // 2.00% (en basis points)
 const SILVER_APY: i64 = 400; // 4.00%
 const GOLD_APY: i64 = 600; // 6.00%
 const PLATINUM_APY: i64 = 800; // 8.00%
+/// Límites de riesgo del piloto, en unidades del asset (7 decimales)
+const UNIT: i128 = 10_000_000;
+const BRONZE_CAP: i128 = 5_000 * UNIT;
+const SILVER_CAP: i128 = 15_000 * UNIT;
+const GOLD_CAP: i128 = 30_000 * UNIT;
+const PLATINUM_CAP: i128 = 50_000 * UNIT;
+const DEFAULT_TVL_CAP: i128 = 1_000_000 * UNIT;
+/// Programa de referidos por defecto
+const DEFAULT_REFERRAL_MIN_BALANCE: i128 = 100 * UNIT;
+const DEFAULT_REFERRAL_MIN_DAYS: u32 = 30;
+const DEFAULT_REFERRAL_REWARD: i128 = 5 * UNIT;
+/// Cada cuánto se vuelve a consultar el nivel en user-levels por defecto
+const DEFAULT_LEVEL_REFRESH_SECS: u64 = 60 * 60;
+/// Tope por defecto del APY configurable
+const DEFAULT_MAX_APY: i64 = 2_000; // 20.00%
 
+/// Base del vault: todas las shares crecen a la tasa Bronze;
+/// los niveles superiores reciben la diferencia como shares de recompensa.
+const BASE_LEVEL: u32 = 1;
+const BPS: i128 = 10_000;
+const SECONDS_PER_YEAR: i128 = 365 * 24 * 60 * 60;
+/// Denominador del interés en punto fijo: APY (bps) * segundos / YEAR_BPS
+const YEAR_BPS: i128 = BPS * SECONDS_PER_YEAR;
+/// Bono sobre el APY del nivel para depósitos a plazo (basis points)
+const TERM_30_BONUS: i64 = 50; // +0.50%
+const TERM_90_BONUS: i64 = 150; // +1.50%
+const TERM_180_BONUS: i64 = 300; // +3.00%
+/// Penalización sobre el principal al romper un plazo antes del vencimiento
+const EARLY_WITHDRAWAL_PENALTY_BPS: i128 = 500; // 5.00%
+// Máximo de depósitos a plazo abiertos por usuario (acota el Vec en storage)
+const MAX_OPEN_TERMS: u32 = 10;
+// Máximo de cambios de APY guardados por nivel (acota el calendario en storage)
+const MAX_APY_CHANGES: u32 = 16;
+// Máximo de referidos por usuario (acota el Vec en storage y las vistas)
+const MAX_REFERRALS: u32 = 50;
+/// Escala de los factores de crecimiento (1.0 = WAD)
+const WAD: i128 = 1_000_000_000_000_000_000;
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+/// Duración aproximada de un ledger de Stellar
+const SECONDS_PER_LEDGER: u64 = 5;
+
+/// a * b / denominator con aritmética i128 verificada; retorna (cociente, residuo)
+fn mul_div(a: i128, b: i128, denominator: i128) -> Result<(i128, i128), Error> {
+    let product = a.checked_mul(b).ok_or(Error::ArithmeticOverflow)?;
+    Ok((product / denominator, product % denominator))
+}
+
+fn checked_add(a: i128, b: i128) -> Result<i128, Error> {
+    a.checked_add(b).ok_or(Error::ArithmeticOverflow)
+}
+
+/// Tasa simple `apy * seconds / año` escalada a WAD
+fn simple_rate_wad(apy: i64, seconds: u64) -> Result<i128, Error> {
+    let rate = (apy as i128)
+        .checked_mul(seconds as i128)
+        .ok_or(Error::ArithmeticOverflow)?;
+    Ok(mul_div(WAD, rate, YEAR_BPS)?.0)
+}
+
+/// base^exp en punto fijo WAD (exponenciación por cuadrados)
+fn pow_wad(mut base: i128, mut exp: u64) -> Result<i128, Error> {
+    let mut result = WAD;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = mul_div(result, base, WAD)?.0;
+        }
+        exp >>= 1;
+        if exp > 0 {
+            base = mul_div(base, base, WAD)?.0;
+        }
+    }
+    Ok(result)
+}
+
+/// e^x en punto fijo WAD mediante serie de Taylor
+fn exp_wad(x: i128) -> Result<i128, Error> {
+    let mut sum = WAD;
+    let mut term = WAD;
+    for n in 1..=32i128 {
+        term = mul_div(term, x, WAD)?.0 / n;
+        if term == 0 {
+            break;
+        }
+        sum = checked_add(sum, term)?;
+    }
+    Ok(sum)
+}
+
+/// Factor de crecimiento (WAD) de un APY durante `elapsed` segundos según el modo
+fn growth_factor(mode: CompoundingMode, apy: i64, elapsed: u64) -> Result<i128, Error> {
+    if elapsed == 0 || apy <= 0 {
+        return Ok(WAD);
+    }
+    match mode {
+        CompoundingMode::Simple => checked_add(WAD, simple_rate_wad(apy, elapsed)?),
+        CompoundingMode::Continuous => exp_wad(simple_rate_wad(apy, elapsed)?),
+        CompoundingMode::Daily => compound_periods(apy, elapsed, SECONDS_PER_DAY),
+        CompoundingMode::PerLedger => compound_periods(apy, elapsed, SECONDS_PER_LEDGER),
+    }
+}
+
+/// Capitaliza por periodos completos y aplica interés simple al periodo parcial
+fn compound_periods(apy: i64, elapsed: u64, period: u64) -> Result<i128, Error> {
+    let per_period = checked_add(WAD, simple_rate_wad(apy, period)?)?;
+    let whole = pow_wad(per_period, elapsed / period)?;
+    let partial = checked_add(WAD, simple_rate_wad(apy, elapsed % period)?)?;
+    Ok(mul_div(whole, partial, WAD)?.0)
+}
+
+/// Posición de un usuario en el vault.
+/// No hay migración desde el layout original (`principal`, `interest_earned`,
+/// `level: u8`): esa versión no compilaba (`u8` en un `contracttype`,
+/// `env.invoker()`), así que DeFindex nunca se desplegó con ella.
 #[derive(Clone, Debug)]
 #[soroban_sdk::contracttype]
 pub struct SavingsPosition {
     pub user: Address,
-    pub principal: i128, // Monto principal ahorrado
-    pub interest_earned: i128, // Interés acumulado
-    pub level: u8, // Nivel del usuario (1-4)
+    pub shares: i128, // Shares del vault
+    pub principal: i128, // Monto neto depositado (costo base)
+    pub level: u32, // Nivel del usuario (1-4)
+    pub apy: i64, // APY en basis points
+    pub last_updated: u64, // Último cálculo de shares de recompensa por nivel
+    pub boost_remainder: i128, // Residuo del boost (en 1/WAD unidades)
+    pub level_checked_at: u64, // Última consulta del nivel en user-levels
+}
+
+/// Contabilidad del vault (estilo ERC-4626)
+/// El precio por share es total_assets / total_shares.
+/// El rendimiento se paga desde `reserve` (fondeada por el admin); lo que la
+/// reserva no alcanza a cubrir se registra en `unfunded_yield` y no se acredita.
+#[derive(Clone, Debug)]
+#[soroban_sdk::contracttype]
+pub struct VaultState {
+    pub total_assets: i128,
+    pub total_shares: i128,
+    pub reserve: i128,
+    pub unfunded_yield: i128,
+    pub yield_remainder: i128, // Residuo del rendimiento base (en 1/WAD unidades)
+    pub term_liabilities: i128, // Principal + interés comprometido de depósitos a plazo
+    pub last_accrual: u64,
+}
+
+/// Cambio de APY programado para un nivel; rige desde `effective_at`
+#[derive(Clone, Debug)]
+#[soroban_sdk::contracttype]
+pub struct RateChange {
+    pub effective_at: u64,
     pub apy: i64, // APY en basis points
+}
+
+/// Reglas del programa de referidos
+/// El referido debe mantener `min_balance` durante `min_days` para que ambos
+/// reciban su recompensa única desde la reserva.
+#[derive(Clone, Debug)]
+#[soroban_sdk::contracttype]
+pub struct ReferralConfig {
+    pub min_balance: i128,
+    pub min_days: u32,
+    pub referrer_reward: i128,
+    pub referee_reward: i128,
+}
+
+/// Referido registrado en el primer depósito del usuario
+#[derive(Clone, Debug)]
+#[soroban_sdk::contracttype]
+pub struct Referral {
+    pub referrer: Address,
+    pub referee: Address,
+    pub qualified_since: Option<u64>, // Desde cuándo mantiene el balance mínimo
+    pub rewarded: bool,
+}
+
+/// Intervalo en que el vault estuvo en pausa; no genera rendimiento.
+/// `end` es u64::MAX mientras la pausa sigue activa. Cada ventana se guarda en
+/// su propia entrada persistente, ordenadas por `start`.
+#[derive(Clone, Debug)]
+#[soroban_sdk::contracttype]
+pub struct PauseWindow {
+    pub start: u64,
+    pub end: u64,
+}
+
+/// Depósito a plazo fijo (30, 90 o 180 días).
+/// El interés se calcula al abrir con APY del nivel + bono del plazo y se
+/// aparta de la reserva, por lo que queda garantizado al vencimiento.
+#[derive(Clone, Debug)]
+#[soroban_sdk::contracttype]
+pub struct TermPosition {
+    pub id: u32,
+    pub principal: i128,
+    pub interest: i128, // Interés a pagar al vencimiento
+    pub apy: i64, // APY del nivel + bono del plazo (basis points)
+    pub term_days: u32,
+    pub opened_at: u64,
+    pub maturity: u64,
+}
+
+/// Modo de capitalización del rendimiento
+/// Simple: interés lineal sobre el periodo transcurrido
+/// Continuous: aproximación de e^(APY * t)
+/// Daily: capitalización diaria
+/// PerLedger: capitalización por ledger (~5 segundos)
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[soroban_sdk::contracttype]
+pub enum CompoundingMode {
+    Simple,
+    Continuous,
+    Daily,
+    PerLedger,
+}
+
+/// Respuesta de `UserLevels::get_user_level` (mismo layout que en user-levels)
+#[derive(Clone, Debug)]
+#[soroban_sdk::contracttype]
+pub struct UserLevelData {
+    pub level: u32,
+    pub goals_achieved: u32,
+    pub courses_completed: u32,
     pub last_updated: i64,
 }
 
+/// Cliente tipado del contrato user-levels
+#[contractclient(name = "UserLevelsClient")]
+pub trait UserLevelsInterface {
+    fn get_user_level(env: Env, user: Address) -> Option<UserLevelData>;
+}
+
+/// Interfaz común de las estrategias a las que DeFindex envía fondos
+/// (Blend, Soroswap, o `mock-strategy` en pruebas).
+/// El vault transfiere el asset a la estrategia antes de llamar `deposit`.
+#[contractclient(name = "StrategyClient")]
+pub trait Strategy {
+    /// Registra `amount` del asset ya transferido por el vault
+    fn deposit(env: Env, amount: i128);
+    /// Devuelve `amount` del asset al vault; retorna lo enviado
+    fn withdraw(env: Env, amount: i128) -> i128;
+    /// Assets que la estrategia mantiene a nombre del vault
+    fn balance(env: Env) -> i128;
+    /// Envía al vault el rendimiento realizado; retorna el monto
+    fn harvest(env: Env) -> i128;
+}
+
+/// Cómo se expresa un retiro flexible: en assets o en shares exactas
+enum Exit {
+    Assets(i128),
+    Shares(i128),
+}
+
+/// Peso de asignación de una estrategia sobre total_assets (en basis points)
+#[derive(Clone, Debug)]
+#[soroban_sdk::contracttype]
+pub struct StrategyAllocation {
+    pub strategy: Address,
+    pub weight_bps: u32,
+}
+
+/// Vista de solvencia del vault
+#[derive(Clone, Debug)]
+#[soroban_sdk::contracttype]
+pub struct Solvency {
+    pub held: i128, // Balance del asset en el contrato y en estrategias
+    pub liabilities: i128, // total_assets + depósitos a plazo adeudados
+    pub reserve: i128, // Reserva de rendimiento sin asignar
+    pub unfunded_yield: i128, // Rendimiento que la reserva no cubrió
+    pub solvent: bool, // held >= liabilities + reserve
+}
+
 #[contracterror]
 #[repr(u32)]
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -35,6 +292,31 @@ pub enum Error {
     PositionNotFound = 3,
     NotAuthorized = 4,
     InsufficientBalance = 5,
+    NotVerifiedStudent = 6,
+    AlreadyInitialized = 7,
+    AssetNotConfigured = 8,
+    InsufficientLiquidity = 9,
+    ArithmeticOverflow = 10,
+    InvalidAllocation = 11,
+    InvalidTerm = 12,
+    TermNotMatured = 13,
+    TermAlreadyMatured = 14,
+    InsufficientReserve = 15,
+    TooManyTerms = 16,
+    InvalidLevel = 17,
+    ApyAboveMax = 18,
+    UserLevelsCallFailed = 19,
+    ExceedsUserCap = 20,
+    ExceedsTvlCap = 21,
+    Paused = 22,
+    NotPaused = 23,
+    InvalidReferral = 24,
+    AlreadyReferred = 25,
+    ReferralNotFound = 26,
+    ReferralNotQualified = 27,
+    StrategyCallFailed = 28,
+    TooManyApyChanges = 29,
+    TooManyReferrals = 30,
 }
 
 #[contract]
@@ -42,6 +324,22 @@ pub struct DeFindex;
 
 #[contractimpl]
 impl DeFindex {
+    /// Fija el admin del vault. Se llama una sola vez, en la misma transacción
+    /// del despliegue, para que nadie más pueda reclamar el rol.
+    pub fn initialize(env: Env, admin: Address) -> Result<(), Error> {
+        if env.storage().instance().has(&Self::admin_key()) {
+            return Err(Error::AlreadyInitialized);
+        }
+        admin.require_auth();
+        env.storage().instance().set(&Self::admin_key(), &admin);
+        Ok(())
+    }
+
+    /// Admin actual del vault
+    pub fn get_admin(env: Env) -> Option<Address> {
+        env.storage().instance().get(&Self::admin_key())
+    }
+
     /// Configura la dirección del contrato user-levels
     pub fn set_user_levels(env: Env, user_levels: Address) {
         Self::assert_admin(&env);
@@ -50,189 +348,1623 @@ impl DeFindex {
             .set(&Self::user_levels_key(), &user_levels);
     }
 
-    /// Deposita fondos en DeFindex para generar rendimientos
-    pub fn deposit(env: Env, amount: i128) -> Result<(), Error> {
-        if amount <= 0 {
-            return Err(Error::InvalidAmount);
-        }
+    /// Configura cada cuántos segundos se vuelve a consultar el nivel de un usuario
+    pub fn set_level_refresh_interval(env: Env, seconds: u64) {
+        Self::assert_admin(&env);
+        env.storage()
+            .instance()
+            .set(&Self::level_refresh_key(), &seconds);
+    }
 
-        let user = env.invoker();
-        
-        // Obtener nivel del usuario
-        let level = Self::get_user_level(&env, &user)?;
-        let apy = Self::get_apy_for_level(level);
+    /// Intervalo de refresco del nivel cacheado en las posiciones
+    pub fn get_level_refresh_interval(env: Env) -> u64 {
+        env.storage()
+            .instance()
+            .get(&Self::level_refresh_key())
+            .unwrap_or(DEFAULT_LEVEL_REFRESH_SECS)
+    }
 
-        // Obtener o crear posición
-        let mut position = Self::get_position(&env, &user)
-            .unwrap_or_else(|| SavingsPosition {
-                user: user.clone(),
-                principal: 0,
-                interest_earned: 0,
-                level,
-                apy,
-                last_updated: env.ledger().timestamp(),
-            });
+    /// Configura el guardián que puede pausar el vault
+    pub fn set_guardian(env: Env, guardian: Address) {
+        Self::assert_admin(&env);
+        env.storage().instance().set(&Self::guardian_key(), &guardian);
+    }
 
-        // Actualizar interés antes de agregar principal
-        position = Self::update_interest(&env, &position)?;
+    /// Pausa depósitos, retiros normales, movimientos de estrategias y el rendimiento
+    /// (guardián o admin). Los usuarios pueden seguir usando `emergency_withdraw`.
+    pub fn pause(env: Env, caller: Address) -> Result<(), Error> {
+        Self::assert_guardian(&env, &caller);
+        if Self::is_paused(env.clone()) {
+            return Err(Error::Paused);
+        }
 
-        // Agregar al principal
-        position.principal += amount;
-        position.level = level;
-        position.apy = apy;
-        position.last_updated = env.ledger().timestamp();
+        let count = Self::pause_count(&env);
+        let window = PauseWindow {
+            start: env.ledger().timestamp(),
+            end: u64::MAX,
+        };
+        env.storage()
+            .persistent()
+            .set(&Self::pause_window_key(count), &window);
+        env.storage()
+            .instance()
+            .set(&Self::pause_count_key(), &(count + 1));
+
+        env.events()
+            .publish((symbol_short!("paused"), caller), env.ledger().timestamp());
+        Ok(())
+    }
 
+    /// Reanuda la operación normal del vault (guardián o admin)
+    pub fn unpause(env: Env, caller: Address) -> Result<(), Error> {
+        Self::assert_guardian(&env, &caller);
+        let last_index = Self::pause_count(&env)
+            .checked_sub(1)
+            .ok_or(Error::NotPaused)?;
+        let mut last = Self::pause_window(&env, last_index);
+        if last.end != u64::MAX {
+            return Err(Error::NotPaused);
+        }
+
+        last.end = env.ledger().timestamp();
         env.storage()
             .persistent()
-            .set(&Self::position_key(&user), &position);
+            .set(&Self::pause_window_key(last_index), &last);
 
+        env.events()
+            .publish((symbol_short!("unpaused"), caller), env.ledger().timestamp());
         Ok(())
     }
 
-    /// Retira fondos de DeFindex
-    pub fn withdraw(env: Env, amount: i128) -> Result<i128, Error> {
-        if amount <= 0 {
-            return Err(Error::InvalidAmount);
+    /// Indica si el vault está en pausa
+    pub fn is_paused(env: Env) -> bool {
+        match Self::pause_count(&env).checked_sub(1) {
+            Some(last_index) => Self::pause_window(&env, last_index).end == u64::MAX,
+            None => false,
         }
+    }
 
-        let user = env.invoker();
-        let mut position = Self::get_position(&env, &user)
-            .ok_or(Error::PositionNotFound)?;
+    /// Retira todo el principal del usuario sin acumular rendimiento pendiente.
+    /// Solo disponible en pausa (si no, falla con NotPaused): las shares se pagan
+    /// al precio ya acumulado, los plazos devuelven solo el principal y el interés
+    /// regresa a la reserva. Fuera de pausa se usan `withdraw` y
+    /// `emergency_withdraw_term`, que cobra la penalización.
+    pub fn emergency_withdraw(env: Env, user: Address) -> Result<i128, Error> {
+        user.require_auth();
+        if !Self::is_paused(env.clone()) {
+            return Err(Error::NotPaused);
+        }
+        let asset = Self::get_asset(&env)?;
+        let mut vault = Self::stored_vault(&env);
 
-        // Actualizar interés antes de retirar
-        position = Self::update_interest(&env, &position)?;
+        let mut payout: i128 = 0;
+        if let Some(position) = Self::get_position(env.clone(), user.clone()) {
+            let assets = Self::to_assets(&vault, position.shares)?;
+            vault.total_shares -= position.shares;
+            vault.total_assets -= assets;
+            payout = checked_add(payout, assets)?;
+            env.storage().persistent().remove(&Self::position_key(&user));
+        }
 
-        let total_balance = position.principal + position.interest_earned;
-        if amount > total_balance {
-            return Err(Error::InsufficientBalance);
+        for term in Self::get_terms(&env, &user).iter() {
+            vault.term_liabilities -= term.principal + term.interest;
+            vault.reserve = checked_add(vault.reserve, term.interest)?;
+            payout = checked_add(payout, term.principal)?;
         }
+        Self::save_terms(&env, &user, &Vec::new(&env));
+        Self::track_referral(&env, &user, 0);
 
-        // Retirar del principal primero, luego del interés
-        if amount <= position.principal {
-            position.principal -= amount;
-        } else {
-            let remaining = amount - position.principal;
-            position.interest_earned -= remaining;
-            position.principal = 0;
+        if payout <= 0 {
+            return Err(Error::PositionNotFound);
         }
 
-        position.last_updated = env.ledger().timestamp();
+        Self::pay_out(&env, &asset, &vault, &user, payout)?;
+        Self::save_vault(&env, &vault);
+
+        env.events()
+            .publish((symbol_short!("emrg_wd"), user), payout);
 
+        Ok(payout)
+    }
+
+    /// Configura las reglas del programa de referidos (solo admin)
+    pub fn set_referral_config(env: Env, config: ReferralConfig) -> Result<(), Error> {
+        Self::assert_admin(&env);
+        if config.min_balance < 0 || config.referrer_reward < 0 || config.referee_reward < 0 {
+            return Err(Error::InvalidAmount);
+        }
+        env.storage()
+            .instance()
+            .set(&Self::referral_config_key(), &config);
+        Ok(())
+    }
+
+    /// Reglas vigentes del programa de referidos
+    pub fn get_referral_config(env: Env) -> ReferralConfig {
         env.storage()
+            .instance()
+            .get(&Self::referral_config_key())
+            .unwrap_or(ReferralConfig {
+                min_balance: DEFAULT_REFERRAL_MIN_BALANCE,
+                min_days: DEFAULT_REFERRAL_MIN_DAYS,
+                referrer_reward: DEFAULT_REFERRAL_REWARD,
+                referee_reward: DEFAULT_REFERRAL_REWARD,
+            })
+    }
+
+    /// Paga las recompensas de un referido que cumplió el balance mínimo durante
+    /// el plazo requerido. Cualquiera puede llamarlo; se paga una sola vez.
+    /// Retorna el total pagado (referidor + referido).
+    pub fn claim_referral_rewards(env: Env, referee: Address) -> Result<i128, Error> {
+        Self::assert_not_paused(&env)?;
+        let asset = Self::get_asset(&env)?;
+        let config = Self::get_referral_config(env.clone());
+
+        let mut referral: Referral = env
+            .storage()
             .persistent()
-            .set(&Self::position_key(&user), &position);
+            .get(&Self::referral_key(&referee))
+            .ok_or(Error::ReferralNotFound)?;
 
-        Ok(amount)
+        if referral.rewarded || !Self::referral_matured(&env, &referral, &config) {
+            return Err(Error::ReferralNotQualified);
+        }
+        // El balance debe seguir por encima del mínimo al cobrar
+        if Self::get_balance(env.clone(), referee.clone()) < config.min_balance {
+            return Err(Error::ReferralNotQualified);
+        }
+
+        let total = checked_add(config.referrer_reward, config.referee_reward)?;
+        let mut vault = Self::accrue_vault(&env)?;
+        if total > vault.reserve {
+            return Err(Error::InsufficientReserve);
+        }
+        vault.reserve -= total;
+
+        referral.rewarded = true;
+        env.storage()
+            .persistent()
+            .set(&Self::referral_key(&referee), &referral);
+        Self::save_vault(&env, &vault);
+
+        Self::pay_out(
+            &env,
+            &asset,
+            &vault,
+            &referral.referrer,
+            config.referrer_reward,
+        )?;
+        Self::pay_out(&env, &asset, &vault, &referee, config.referee_reward)?;
+
+        env.events().publish(
+            (symbol_short!("ref_paid"), referral.referrer, referee),
+            total,
+        );
+
+        Ok(total)
     }
 
-    /// Obtiene la posición de ahorros de un usuario
-    pub fn get_position(env: Env, user: Address) -> Option<SavingsPosition> {
-        env.storage().persistent().get(&Self::position_key(&user))
+    /// Usuarios referidos por `user` (como máximo `MAX_REFERRALS`)
+    pub fn referrals_of(env: Env, user: Address) -> Vec<Address> {
+        env.storage()
+            .persistent()
+            .get(&Self::referrals_of_key(&user))
+            .unwrap_or(Vec::new(&env))
     }
 
-    /// Obtiene el balance total (principal + interés) de un usuario
-    pub fn get_balance(env: Env, user: Address) -> i128 {
-        if let Some(mut position) = Self::get_position(env.clone(), user) {
-            // Actualizar interés antes de retornar
-            if let Ok(updated) = Self::update_interest(&env, &position) {
-                position = updated;
+    /// Recompensas aún no pagadas de referidos que mantienen el balance mínimo,
+    /// tanto como referidor como referido (cobrables al cumplir `min_days`)
+    pub fn pending_referral_rewards(env: Env, user: Address) -> i128 {
+        let config = Self::get_referral_config(env.clone());
+        let mut pending: i128 = 0;
+
+        for referee in Self::referrals_of(env.clone(), user.clone()).iter() {
+            if let Some(referral) = Self::get_referral(&env, &referee) {
+                if !referral.rewarded && referral.qualified_since.is_some() {
+                    pending = pending.saturating_add(config.referrer_reward);
+                }
+            }
+        }
+
+        if let Some(referral) = Self::get_referral(&env, &user) {
+            if !referral.rewarded && referral.qualified_since.is_some() {
+                pending = pending.saturating_add(config.referee_reward);
             }
-            position.principal + position.interest_earned
-        } else {
-            0
         }
+
+        pending
     }
 
-    /// Calcula y actualiza el interés acumulado
-    fn update_interest(
-        env: &Env,
-        position: &SavingsPosition,
-    ) -> Result<SavingsPosition, Error> {
-        let current_time = env.ledger().timestamp();
-        let time_elapsed = current_time - position.last_updated;
+    /// Configura el registro de estudiantes. Si está configurado, solo
+    /// estudiantes verificados pueden depositar.
+    pub fn set_student_registry(env: Env, student_registry: Address) {
+        Self::assert_admin(&env);
+        env.storage()
+            .instance()
+            .set(&Self::student_registry_key(), &student_registry);
+    }
+
+    /// Configura el asset SEP-41 que custodia el vault
+    pub fn set_asset(env: Env, asset: Address) {
+        Self::assert_admin(&env);
+        env.storage().instance().set(&Self::asset_key(), &asset);
+    }
 
-        if time_elapsed <= 0 || position.principal == 0 {
-            return Ok(position.clone());
+    /// Programa el APY de un nivel a partir de `effective_at` (solo admin).
+    /// Un `effective_at` en el pasado rige desde ahora.
+    /// El calendario guarda como máximo `MAX_APY_CHANGES` cambios: al pasarse se
+    /// descartan los más antiguos ya superados, y una posición sin tocar desde
+    /// antes de ellos acumula ese tramo con el APY por defecto del nivel. Si todos
+    /// los cambios siguen pendientes falla con TooManyApyChanges.
+    pub fn set_level_apy(env: Env, level: u32, apy: i64, effective_at: u64) -> Result<(), Error> {
+        Self::assert_admin(&env);
+        if !(1..=4).contains(&level) {
+            return Err(Error::InvalidLevel);
+        }
+        if apy < 0 {
+            return Err(Error::InvalidAmount);
+        }
+        if apy > Self::get_max_apy(env.clone()) {
+            return Err(Error::ApyAboveMax);
         }
 
-        // Calcular interés: principal * APY * time_elapsed / (365 * 24 * 60 * 60)
-        // APY está en basis points (100 = 1%)
-        let seconds_per_year = 365 * 24 * 60 * 60;
-        let interest = (position.principal as i64 * position.apy * time_elapsed)
-            / (10000 * seconds_per_year);
+        // Acumular el rendimiento base con la tasa anterior antes de tocar el calendario
+        Self::accrue_vault(&env)?;
 
-        let mut updated = position.clone();
-        updated.interest_earned += interest as i128;
-        updated.last_updated = current_time;
+        let now = env.ledger().timestamp();
+        let effective_at = effective_at.max(now);
 
-        // Actualizar APY si el nivel cambió
-        let current_level = Self::get_user_level(env, &position.user)?;
-        if current_level != position.level {
-            updated.level = current_level;
-            updated.apy = Self::get_apy_for_level(current_level);
+        // Se conserva el historial para poder dividir intervalos pasados;
+        // un cambio con el mismo effective_at reemplaza al anterior
+        let mut schedule = Vec::new(&env);
+        for change in Self::apy_schedule(&env, level).iter() {
+            if change.effective_at != effective_at {
+                schedule.push_back(change);
+            }
         }
 
-        Ok(updated)
+        let position = schedule
+            .iter()
+            .position(|change| change.effective_at > effective_at)
+            .unwrap_or(schedule.len() as usize);
+        schedule.insert(position as u32, RateChange { effective_at, apy });
+
+        // El cambio vigente y los pendientes se conservan siempre
+        while schedule.len() > MAX_APY_CHANGES {
+            let superseded = schedule.len() > 1 && schedule.get_unchecked(1).effective_at <= now;
+            if !superseded {
+                return Err(Error::TooManyApyChanges);
+            }
+            schedule.pop_front();
+        }
+
+        env.storage()
+            .instance()
+            .set(&Self::apy_schedule_key(level), &schedule);
+        env.events()
+            .publish((symbol_short!("apy_set"), level), (apy, effective_at));
+
+        Ok(())
+    }
+
+    /// Configura el APY máximo permitido (solo admin)
+    pub fn set_max_apy(env: Env, max_apy: i64) -> Result<(), Error> {
+        Self::assert_admin(&env);
+        if max_apy < 0 {
+            return Err(Error::ApyAboveMax);
+        }
+        Self::accrue_vault(&env)?;
+        env.storage().instance().set(&Self::max_apy_key(), &max_apy);
+        env.events().publish((symbol_short!("apy_max"),), max_apy);
+        Ok(())
     }
 
-    fn get_user_level(env: &Env, user: &Address) -> Result<u8, Error> {
-        let user_levels: Address = env
-            .storage()
+    /// APY máximo permitido
+    pub fn get_max_apy(env: Env) -> i64 {
+        env.storage()
             .instance()
-            .get(&Self::user_levels_key())
-            .ok_or(Error::UserLevelsNotConfigured)?;
+            .get(&Self::max_apy_key())
+            .unwrap_or(DEFAULT_MAX_APY)
+    }
 
-        use soroban_sdk::Vec;
-        let mut args = Vec::new(env);
-        args.push_back(user.clone().into_val(env));
+    /// APY vigente para un nivel (1=Bronze, 2=Silver, 3=Gold, 4=Platinum)
+    pub fn get_apy_for_level(env: Env, level: u32) -> i64 {
+        Self::apy_at(&env, level, env.ledger().timestamp())
+    }
 
-        let result = env.invoke_contract(
-            &user_levels,
-            &symbol_short!("get_lvl"), // get_user_level_value
-            args,
-        );
+    /// Calendario de cambios de APY de un nivel (vigente + futuros)
+    pub fn get_apy_schedule(env: Env, level: u32) -> Vec<RateChange> {
+        Self::apy_schedule(&env, level)
+    }
 
-        Ok(result.try_into_val(env).unwrap_or(1)) // Bronze por defecto
+    /// Configura el principal máximo por usuario para un nivel (solo admin)
+    pub fn set_user_cap(env: Env, level: u32, cap: i128) -> Result<(), Error> {
+        Self::assert_admin(&env);
+        if !(1..=4).contains(&level) {
+            return Err(Error::InvalidLevel);
+        }
+        if cap < 0 {
+            return Err(Error::InvalidAmount);
+        }
+        env.storage()
+            .instance()
+            .set(&Self::user_cap_key(level), &cap);
+        Ok(())
     }
 
-    fn get_apy_for_level(level: u8) -> i64 {
-        match level {
-            1 => BRONZE_APY,   // Bronze: 2%
-            2 => SILVER_APY,   // Silver: 4%
-            3 => GOLD_APY,     // Gold: 6%
-            4 => PLATINUM_APY, // Platinum: 8%
-            _ => BRONZE_APY,
+    /// Configura el límite global de valor depositado (solo admin)
+    pub fn set_tvl_cap(env: Env, cap: i128) -> Result<(), Error> {
+        Self::assert_admin(&env);
+        if cap < 0 {
+            return Err(Error::InvalidAmount);
         }
+        env.storage().instance().set(&Self::tvl_cap_key(), &cap);
+        Ok(())
     }
 
-    fn position_key(user: &Address) -> (Symbol, Address) {
-        (symbol_short!("pos"), user.clone())
+    /// Principal máximo por usuario para un nivel
+    pub fn get_user_cap(env: Env, level: u32) -> i128 {
+        env.storage()
+            .instance()
+            .get(&Self::user_cap_key(level))
+            .unwrap_or(match level {
+                2 => SILVER_CAP,
+                3 => GOLD_CAP,
+                4 => PLATINUM_CAP,
+                _ => BRONZE_CAP,
+            })
     }
 
-    fn user_levels_key() -> Symbol {
-        symbol_short!("usr_lvl")
+    /// Límite global de valor depositado
+    pub fn get_tvl_cap(env: Env) -> i128 {
+        env.storage()
+            .instance()
+            .get(&Self::tvl_cap_key())
+            .unwrap_or(DEFAULT_TVL_CAP)
     }
 
-    fn admin_key() -> Symbol {
-        symbol_short!("admin")
+    /// Principal que el usuario todavía puede depositar según su nivel cacheado
+    pub fn remaining_user_capacity(env: Env, user: Address) -> i128 {
+        let position = Self::get_position(env.clone(), user.clone());
+        let level = position.as_ref().map(|p| p.level).unwrap_or(1);
+        let principal = Self::user_principal(
+            &env,
+            &user,
+            position.map(|p| p.principal).unwrap_or(0),
+        );
+        (Self::get_user_cap(env, level) - principal).max(0)
     }
 
-    fn assert_admin(env: &Env) {
-        let admin: Address = env
-            .storage()
+    /// Valor que el vault todavía puede aceptar antes del límite global
+    pub fn remaining_tvl_capacity(env: Env) -> i128 {
+        let vault = Self::view_vault(&env);
+        (Self::get_tvl_cap(env) - Self::tvl(&vault)).max(0)
+    }
+
+    /// Configura el modo de capitalización. El rendimiento pendiente se acumula
+    /// con el modo anterior antes del cambio.
+    pub fn set_compounding_mode(env: Env, mode: CompoundingMode) -> Result<(), Error> {
+        Self::assert_admin(&env);
+        Self::accrue_vault(&env)?;
+        env.storage()
             .instance()
-            .get(&Self::admin_key())
-            .unwrap_or_else(|| {
-                let invoker = env.invoker();
-                env.storage()
-                    .instance()
-                    .set(&Self::admin_key(), &invoker);
-                invoker
-            });
+            .set(&Self::compounding_key(), &mode);
+        Ok(())
+    }
+
+    /// Modo de capitalización vigente
+    pub fn get_compounding_mode(env: Env) -> CompoundingMode {
+        env.storage()
+            .instance()
+            .get(&Self::compounding_key())
+            .unwrap_or(CompoundingMode::Simple)
+    }
+
+    /// Fondea la reserva de rendimiento con tokens del admin.
+    /// Retorna el nuevo balance de la reserva.
+    pub fn fund_reserve(env: Env, amount: i128) -> Result<i128, Error> {
+        let admin = Self::assert_admin(&env);
+        if amount <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+
+        let asset = Self::get_asset(&env)?;
+        token::Client::new(&env, &asset).transfer(
+            &admin,
+            &env.current_contract_address(),
+            &amount,
+        );
 
-        if env.invoker() != admin {
-            panic_with_error(env, &Error::NotAuthorized);
+        let mut vault = Self::accrue_vault(&env)?;
+        vault.reserve = checked_add(vault.reserve, amount)?;
+        Self::save_vault(&env, &vault);
+
+        env.events()
+            .publish((symbol_short!("reserve"), admin), amount);
+
+        Ok(vault.reserve)
+    }
+
+    /// Balance de la reserva de rendimiento
+    pub fn reserve_balance(env: Env) -> i128 {
+        Self::view_vault(&env).reserve
+    }
+
+    /// Vista de solvencia: compara los tokens custodiados con lo adeudado
+    pub fn solvency(env: Env) -> Solvency {
+        let vault = Self::view_vault(&env);
+        let idle = match Self::get_asset(&env) {
+            Ok(asset) => token::Client::new(&env, &asset).balance(&env.current_contract_address()),
+            Err(_) => 0,
+        };
+        let held = idle.saturating_add(Self::deployed_assets(&env));
+
+        Solvency {
+            held,
+            liabilities: vault.total_assets.saturating_add(vault.term_liabilities),
+            reserve: vault.reserve,
+            unfunded_yield: vault.unfunded_yield,
+            solvent: held
+                >= vault
+                    .total_assets
+                    .saturating_add(vault.term_liabilities)
+                    .saturating_add(vault.reserve),
         }
     }
+
+    /// Deposita fondos en DeFindex para generar rendimientos.
+    /// Acuña shares al precio por share actual y retorna las shares acuñadas.
+    /// En el primer depósito se puede indicar quién refirió al usuario.
+    /// Requiere la autorización de `user`; otros contratos (ej. savings-goals)
+    /// depositan con su propia dirección.
+    pub fn deposit(
+        env: Env,
+        user: Address,
+        amount: i128,
+        referrer: Option<Address>,
+    ) -> Result<i128, Error> {
+        user.require_auth();
+        Self::deposit_internal(env, user, amount, referrer)
+    }
+
+    /// Retira `amount` en assets de DeFindex quemando las shares necesarias.
+    /// Requiere la autorización de `user`; los fondos van a `user`.
+    pub fn withdraw(env: Env, user: Address, amount: i128) -> Result<i128, Error> {
+        user.require_auth();
+        Self::withdraw_internal(env, user, Exit::Assets(amount))
+    }
+
+    /// Quema exactamente `shares` y paga su valor en assets (redondeado hacia
+    /// abajo). Lo usan integradores que llevan la cuenta en shares, como
+    /// savings-goals por meta. Requiere la autorización de `user`.
+    pub fn redeem(env: Env, user: Address, shares: i128) -> Result<i128, Error> {
+        user.require_auth();
+        Self::withdraw_internal(env, user, Exit::Shares(shares))
+    }
+
+    fn deposit_internal(
+        env: Env,
+        user: Address,
+        amount: i128,
+        referrer: Option<Address>,
+    ) -> Result<i128, Error> {
+        Self::assert_not_paused(&env)?;
+        if amount <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+
+        Self::assert_verified_student(&env, &user)?;
+        let asset = Self::get_asset(&env)?;
+
+        let mut vault = Self::accrue_vault(&env)?;
+
+        if let Some(referrer) = referrer {
+            Self::register_referral(&env, &user, referrer)?;
+        }
+
+        // Obtener o crear posición (el nivel se consulta en refresh_level)
+        let mut position = match Self::get_position(env.clone(), user.clone()) {
+            Some(position) => position,
+            None => SavingsPosition {
+                user: user.clone(),
+                shares: 0,
+                principal: 0,
+                level: 1,
+                apy: Self::get_apy_for_level(env.clone(), 1),
+                last_updated: env.ledger().timestamp(),
+                boost_remainder: 0,
+                level_checked_at: 0,
+            },
+        };
+
+        // Acreditar el boost con el nivel cacheado antes de cambiar las shares
+        let is_new = position.shares == 0;
+        Self::apply_level_boost(&env, &mut vault, &mut position)?;
+        Self::refresh_level(&env, &mut position, is_new)?;
+
+        Self::assert_within_caps(&env, &vault, &user, position.principal, position.level, amount)?;
+
+        let shares = Self::to_shares(&vault, amount, false)?;
+        if shares <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+
+        position.shares = checked_add(position.shares, shares)?;
+        position.principal = checked_add(position.principal, amount)?;
+        vault.total_shares = checked_add(vault.total_shares, shares)?;
+        vault.total_assets = checked_add(vault.total_assets, amount)?;
+
+        token::Client::new(&env, &asset).transfer(
+            &user,
+            &env.current_contract_address(),
+            &amount,
+        );
+
+        Self::track_referral(&env, &user, Self::to_assets(&vault, position.shares)?);
+
+        Self::save_vault(&env, &vault);
+        env.storage()
+            .persistent()
+            .set(&Self::position_key(&user), &position);
+
+        Ok(shares)
+    }
+
+    fn withdraw_internal(env: Env, user: Address, exit: Exit) -> Result<i128, Error> {
+        Self::assert_not_paused(&env)?;
+        if matches!(exit, Exit::Assets(value) | Exit::Shares(value) if value <= 0) {
+            return Err(Error::InvalidAmount);
+        }
+
+        let asset = Self::get_asset(&env)?;
+        let mut position = Self::get_position(env.clone(), user.clone())
+            .ok_or(Error::PositionNotFound)?;
+
+        let mut vault = Self::accrue_vault(&env)?;
+        Self::apply_level_boost(&env, &mut vault, &mut position)?;
+
+        // Redondear a favor del vault: hacia arriba las shares, hacia abajo los assets
+        let (shares, amount) = match exit {
+            Exit::Assets(amount) => (Self::to_shares(&vault, amount, true)?, amount),
+            Exit::Shares(shares) => (shares, Self::to_assets(&vault, shares)?),
+        };
+        if shares > position.shares {
+            return Err(Error::InsufficientBalance);
+        }
+        if amount <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+
+        // Traer de las estrategias lo que falte; fallar sin pánico si no alcanza
+        let token = token::Client::new(&env, &asset);
+        Self::ensure_liquidity(&env, &token, &vault, amount)?;
+
+        position.shares -= shares;
+        position.principal = (position.principal - amount).max(0);
+        vault.total_shares -= shares;
+        vault.total_assets -= amount;
+
+        // Actualizar APY si el nivel cambió
+        Self::refresh_level(&env, &mut position, false)?;
+        Self::track_referral(&env, &user, Self::to_assets(&vault, position.shares)?);
+
+        Self::save_vault(&env, &vault);
+        env.storage()
+            .persistent()
+            .set(&Self::position_key(&user), &position);
+
+        token.transfer(&env.current_contract_address(), &user, &amount);
+
+        Ok(amount)
+    }
+
+    /// Abre un depósito a plazo de 30, 90 o 180 días, con hasta `MAX_OPEN_TERMS`
+    /// abiertos por usuario. Retorna el id de la posición.
+    pub fn deposit_term(
+        env: Env,
+        user: Address,
+        amount: i128,
+        term_days: u32,
+    ) -> Result<u32, Error> {
+        user.require_auth();
+        Self::assert_not_paused(&env)?;
+        if amount <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+        let bonus = Self::term_bonus(term_days).ok_or(Error::InvalidTerm)?;
+
+        Self::assert_verified_student(&env, &user)?;
+        let asset = Self::get_asset(&env)?;
+
+        let mut terms = Self::get_terms(&env, &user);
+        if terms.len() >= MAX_OPEN_TERMS {
+            return Err(Error::TooManyTerms);
+        }
+
+        let mut vault = Self::accrue_vault(&env)?;
+
+        let level = Self::fetch_user_level(&env, &user)?;
+        let apy = Self::get_apy_for_level(env.clone(), level) + bonus;
+
+        let flexible_principal = Self::get_position(env.clone(), user.clone())
+            .map(|position| position.principal)
+            .unwrap_or(0);
+        Self::assert_within_caps(&env, &vault, &user, flexible_principal, level, amount)?;
+
+        // Interés fijo del plazo, apartado de la reserva desde ahora
+        let duration = term_days as u64 * SECONDS_PER_DAY;
+        let mode = Self::get_compounding_mode(env.clone());
+        let growth = growth_factor(mode, apy, duration)? - WAD;
+        let interest = mul_div(amount, growth, WAD)?.0;
+        if interest > vault.reserve {
+            return Err(Error::InsufficientReserve);
+        }
+
+        vault.reserve -= interest;
+        vault.term_liabilities = checked_add(vault.term_liabilities, checked_add(amount, interest)?)?;
+
+        let id: u32 = env
+            .storage()
+            .instance()
+            .get(&Self::term_seq_key())
+            .unwrap_or(0)
+            + 1;
+        let now = env.ledger().timestamp();
+        let term = TermPosition {
+            id,
+            principal: amount,
+            interest,
+            apy,
+            term_days,
+            opened_at: now,
+            maturity: now + duration,
+        };
+
+        token::Client::new(&env, &asset).transfer(
+            &user,
+            &env.current_contract_address(),
+            &amount,
+        );
+
+        terms.push_back(term);
+        Self::save_terms(&env, &user, &terms);
+        env.storage().instance().set(&Self::term_seq_key(), &id);
+        Self::save_vault(&env, &vault);
+
+        env.events()
+            .publish((symbol_short!("term_open"), user, id), amount);
+
+        Ok(id)
+    }
+
+    /// Retira un depósito a plazo vencido (principal + interés)
+    pub fn withdraw_term(env: Env, user: Address, term_id: u32) -> Result<i128, Error> {
+        user.require_auth();
+        Self::assert_not_paused(&env)?;
+        let asset = Self::get_asset(&env)?;
+        let mut terms = Self::get_terms(&env, &user);
+        let index = Self::find_term(&terms, term_id).ok_or(Error::PositionNotFound)?;
+        let term = terms.get_unchecked(index);
+
+        if env.ledger().timestamp() < term.maturity {
+            return Err(Error::TermNotMatured);
+        }
+
+        let payout = checked_add(term.principal, term.interest)?;
+        let mut vault = Self::accrue_vault(&env)?;
+        vault.term_liabilities -= payout;
+
+        Self::pay_out(&env, &asset, &vault, &user, payout)?;
+
+        terms.remove(index);
+        Self::save_terms(&env, &user, &terms);
+        Self::save_vault(&env, &vault);
+
+        env.events()
+            .publish((symbol_short!("term_wd"), user, term_id), payout);
+
+        Ok(payout)
+    }
+
+    /// Rompe un depósito a plazo antes del vencimiento: se pierde el interés y se
+    /// paga una penalización sobre el principal, que regresan a la reserva.
+    pub fn emergency_withdraw_term(
+        env: Env,
+        user: Address,
+        term_id: u32,
+    ) -> Result<i128, Error> {
+        user.require_auth();
+        Self::assert_not_paused(&env)?;
+        let asset = Self::get_asset(&env)?;
+        let mut terms = Self::get_terms(&env, &user);
+        let index = Self::find_term(&terms, term_id).ok_or(Error::PositionNotFound)?;
+        let term = terms.get_unchecked(index);
+
+        if env.ledger().timestamp() >= term.maturity {
+            return Err(Error::TermAlreadyMatured);
+        }
+
+        let penalty = mul_div(term.principal, EARLY_WITHDRAWAL_PENALTY_BPS, BPS)?.0;
+        let payout = term.principal - penalty;
+
+        let mut vault = Self::accrue_vault(&env)?;
+        vault.term_liabilities -= term.principal + term.interest;
+        vault.reserve = checked_add(vault.reserve, checked_add(term.interest, penalty)?)?;
+
+        Self::pay_out(&env, &asset, &vault, &user, payout)?;
+
+        terms.remove(index);
+        Self::save_terms(&env, &user, &terms);
+        Self::save_vault(&env, &vault);
+
+        env.events()
+            .publish((symbol_short!("term_brk"), user, term_id), penalty);
+
+        Ok(payout)
+    }
+
+    /// Lista la posición flexible y los depósitos a plazo de un usuario
+    pub fn list_positions(
+        env: Env,
+        user: Address,
+    ) -> (Option<SavingsPosition>, Vec<TermPosition>) {
+        (
+            Self::get_position(env.clone(), user.clone()),
+            Self::get_terms(&env, &user),
+        )
+    }
+
+    /// Obtiene la posición de ahorros de un usuario
+    pub fn get_position(env: Env, user: Address) -> Option<SavingsPosition> {
+        env.storage().persistent().get(&Self::position_key(&user))
+    }
+
+    /// Obtiene el balance total (valor de las shares) de un usuario
+    pub fn get_balance(env: Env, user: Address) -> i128 {
+        if let Some(mut position) = Self::get_position(env.clone(), user) {
+            let mut vault = Self::view_vault(&env);
+            Self::apply_level_boost(&env, &mut vault, &mut position)
+                .and_then(|_| Self::to_assets(&vault, position.shares))
+                .unwrap_or_else(|err| env.panic_with_error(err))
+        } else {
+            0
+        }
+    }
+
+    /// Configura el peso de una estrategia (solo admin). Peso 0 la deja sin
+    /// asignación; el siguiente `rebalance` retira sus fondos.
+    pub fn set_allocation(env: Env, strategy: Address, weight_bps: u32) -> Result<(), Error> {
+        Self::assert_admin(&env);
+
+        let mut allocations = Self::get_allocations(env.clone());
+        let mut total: u32 = weight_bps;
+        let mut found = false;
+        for i in 0..allocations.len() {
+            let mut allocation = allocations.get_unchecked(i);
+            if allocation.strategy == strategy {
+                allocation.weight_bps = weight_bps;
+                allocations.set(i, allocation);
+                found = true;
+            } else {
+                total = total
+                    .checked_add(allocation.weight_bps)
+                    .ok_or(Error::InvalidAllocation)?;
+            }
+        }
+        if !found {
+            allocations.push_back(StrategyAllocation {
+                strategy: strategy.clone(),
+                weight_bps,
+            });
+        }
+
+        if total as i128 > BPS {
+            return Err(Error::InvalidAllocation);
+        }
+
+        env.storage()
+            .instance()
+            .set(&Self::allocations_key(), &allocations);
+        env.events()
+            .publish((symbol_short!("alloc"), strategy), weight_bps);
+
+        Ok(())
+    }
+
+    /// Estrategias configuradas y sus pesos
+    pub fn get_allocations(env: Env) -> Vec<StrategyAllocation> {
+        env.storage()
+            .instance()
+            .get(&Self::allocations_key())
+            .unwrap_or(Vec::new(&env))
+    }
+
+    /// Mueve fondos entre el vault y las estrategias según los pesos (solo admin).
+    /// Primero cosecha el rendimiento hacia la reserva, para que los retiros solo
+    /// muevan principal, y registra las pérdidas de las estrategias; luego retira
+    /// el exceso de cada estrategia y deposita el faltante con la liquidez
+    /// disponible. La reserva se queda en el vault. Falla con StrategyCallFailed
+    /// si una estrategia rechaza el depósito.
+    pub fn rebalance(env: Env) -> Result<(), Error> {
+        Self::assert_not_paused(&env)?;
+        Self::assert_admin(&env);
+        let asset = Self::get_asset(&env)?;
+        let token = token::Client::new(&env, &asset);
+        let mut vault = Self::accrue_vault(&env)?;
+        Self::harvest_strategies(&env, &mut vault)?;
+        Self::reconcile_strategies(&env, &token, &mut vault)?;
+        Self::save_vault(&env, &vault);
+        let allocations = Self::get_allocations(env.clone());
+
+        let mut deficits = Vec::new(&env);
+        for allocation in allocations.iter() {
+            let client = StrategyClient::new(&env, &allocation.strategy);
+            let target = mul_div(vault.total_assets, allocation.weight_bps as i128, BPS)?.0;
+            // Una estrategia que no responde se omite en esta pasada
+            let Ok(Ok(current)) = client.try_balance() else {
+                deficits.push_back(0i128);
+                continue;
+            };
+            if current > target {
+                let _ = client.try_withdraw(&(current - target));
+                deficits.push_back(0i128);
+            } else {
+                deficits.push_back(target - current);
+            }
+        }
+
+        for (i, allocation) in allocations.iter().enumerate() {
+            let deficit = deficits.get_unchecked(i as u32);
+            // La reserva y los depósitos a plazo se quedan líquidos en el vault
+            let available = token.balance(&env.current_contract_address())
+                - vault.reserve
+                - vault.term_liabilities;
+            let amount = deficit.min(available);
+            if amount <= 0 {
+                continue;
+            }
+            // Si la estrategia rechaza el depósito se revierte todo el rebalanceo,
+            // incluida la transferencia, para no dejar tokens sin registrar en ella
+            token.transfer(&env.current_contract_address(), &allocation.strategy, &amount);
+            let client = StrategyClient::new(&env, &allocation.strategy);
+            if !matches!(client.try_deposit(&amount), Ok(Ok(()))) {
+                return Err(Error::StrategyCallFailed);
+            }
+        }
+
+        env.events()
+            .publish((symbol_short!("rebalance"),), vault.total_assets);
+
+        Ok(())
+    }
+
+    /// Cosecha el rendimiento de todas las estrategias hacia la reserva (solo admin).
+    /// Retorna el total cosechado.
+    pub fn harvest(env: Env) -> Result<i128, Error> {
+        Self::assert_not_paused(&env)?;
+        Self::assert_admin(&env);
+        let mut vault = Self::accrue_vault(&env)?;
+        let harvested = Self::harvest_strategies(&env, &mut vault)?;
+        Self::save_vault(&env, &vault);
+
+        env.events()
+            .publish((symbol_short!("harvest"),), harvested);
+
+        Ok(harvested)
+    }
+
+    /// Balance proyectado de un usuario en `at_timestamp` con el calendario de APY
+    /// de su nivel actual y el modo de capitalización vigente
+    /// (calculadora "¿cuánto tendré?"). El nivel se consulta en user-levels, no
+    /// se usa el cacheado en la posición.
+    pub fn project_balance(env: Env, user: Address, at_timestamp: u64) -> Result<i128, Error> {
+        if Self::get_position(env.clone(), user.clone()).is_none() {
+            return Ok(0);
+        }
+
+        let level = Self::fetch_user_level(&env, &user)?;
+        let balance = Self::get_balance(env.clone(), user);
+        let now = env.ledger().timestamp();
+        let factor = Self::growth_between(&env, level, now, at_timestamp.max(now))?;
+
+        Ok(mul_div(balance, factor, WAD)?.0)
+    }
+
+    /// Total de assets administrados por el vault (incluye rendimiento acumulado)
+    pub fn total_assets(env: Env) -> i128 {
+        Self::view_vault(&env).total_assets
+    }
+
+    /// Total de shares emitidas
+    pub fn total_shares(env: Env) -> i128 {
+        Self::view_vault(&env).total_shares
+    }
+
+    /// Shares que se acuñarían por `assets` al precio actual
+    pub fn convert_to_shares(env: Env, assets: i128) -> Result<i128, Error> {
+        Self::to_shares(&Self::current_vault(&env)?, assets, false)
+    }
+
+    /// Assets que valen `shares` al precio actual
+    pub fn convert_to_assets(env: Env, shares: i128) -> Result<i128, Error> {
+        Self::to_assets(&Self::current_vault(&env)?, shares)
+    }
+
+    fn to_shares(vault: &VaultState, assets: i128, round_up: bool) -> Result<i128, Error> {
+        if vault.total_shares == 0 || vault.total_assets == 0 {
+            return Ok(assets);
+        }
+        let (shares, remainder) = mul_div(assets, vault.total_shares, vault.total_assets)?;
+        if round_up && remainder != 0 {
+            checked_add(shares, 1)
+        } else {
+            Ok(shares)
+        }
+    }
+
+    fn to_assets(vault: &VaultState, shares: i128) -> Result<i128, Error> {
+        if vault.total_shares == 0 {
+            return Ok(shares);
+        }
+        Ok(mul_div(shares, vault.total_assets, vault.total_shares)?.0)
+    }
+
+    /// Estado del vault con el rendimiento base pendiente aplicado (sin guardar)
+    fn current_vault(env: &Env) -> Result<VaultState, Error> {
+        let mut vault = Self::stored_vault(env);
+
+        let now = env.ledger().timestamp();
+        let elapsed = now.saturating_sub(vault.last_accrual);
+        if elapsed > 0 && vault.total_assets > 0 {
+            // Rendimiento base: total_assets * (factor - 1) según el modo de capitalización.
+            // El residuo de la división se arrastra al siguiente cálculo para no
+            // perder interés por debajo de una unidad.
+            let growth = Self::growth_between(env, BASE_LEVEL, vault.last_accrual, now)? - WAD;
+            let numerator = checked_add(
+                vault.total_assets.checked_mul(growth).ok_or(Error::ArithmeticOverflow)?,
+                vault.yield_remainder,
+            )?;
+            let interest = numerator / WAD;
+            vault.yield_remainder = numerator % WAD;
+            Self::pay_from_reserve(&mut vault, interest)?;
+        }
+        vault.last_accrual = now;
+        Ok(vault)
+    }
+
+    /// Estado guardado del vault, sin acumular rendimiento
+    fn stored_vault(env: &Env) -> VaultState {
+        env.storage()
+            .instance()
+            .get(&Self::vault_key())
+            .unwrap_or(VaultState {
+                total_assets: 0,
+                total_shares: 0,
+                reserve: 0,
+                unfunded_yield: 0,
+                yield_remainder: 0,
+                term_liabilities: 0,
+                last_accrual: env.ledger().timestamp(),
+            })
+    }
+
+    /// Igual que `current_vault` para vistas: un overflow se reporta como error del contrato
+    fn view_vault(env: &Env) -> VaultState {
+        Self::current_vault(env).unwrap_or_else(|err| env.panic_with_error(err))
+    }
+
+    fn accrue_vault(env: &Env) -> Result<VaultState, Error> {
+        let vault = Self::current_vault(env)?;
+        Self::save_vault(env, &vault);
+        Ok(vault)
+    }
+
+    fn save_vault(env: &Env, vault: &VaultState) {
+        env.storage().instance().set(&Self::vault_key(), vault);
+    }
+
+    /// Acredita el extra por nivel (APY del nivel - APY base) como shares de recompensa
+    /// al precio actual, de modo que no diluye a los demás depositantes.
+    fn apply_level_boost(
+        env: &Env,
+        vault: &mut VaultState,
+        position: &mut SavingsPosition,
+    ) -> Result<(), Error> {
+        let now = env.ledger().timestamp();
+        let from = position.last_updated;
+        position.last_updated = now;
+
+        if now <= from || position.level <= BASE_LEVEL || position.shares == 0 {
+            return Ok(());
+        }
+
+        // Boost = valor * (factor del nivel - factor base), respetando los cambios de tasa
+        let boost_growth = Self::growth_between(env, position.level, from, now)?
+            - Self::growth_between(env, BASE_LEVEL, from, now)?;
+        if boost_growth <= 0 {
+            return Ok(());
+        }
+
+        let value = Self::to_assets(vault, position.shares)?;
+        let numerator = checked_add(
+            value
+                .checked_mul(boost_growth)
+                .ok_or(Error::ArithmeticOverflow)?,
+            position.boost_remainder,
+        )?;
+        let boost_assets = numerator / WAD;
+        position.boost_remainder = numerator % WAD;
+
+        // Sin reserva suficiente el boost se paga solo hasta lo disponible
+        let funded = boost_assets.min(vault.reserve);
+        let boost_shares = Self::to_shares(vault, funded, false)?;
+        if boost_shares <= 0 {
+            // Menos de una share: arrastrar lo fondeable al siguiente cálculo
+            position.boost_remainder = checked_add(
+                position.boost_remainder,
+                funded.checked_mul(WAD).ok_or(Error::ArithmeticOverflow)?,
+            )?;
+            vault.unfunded_yield = checked_add(vault.unfunded_yield, boost_assets - funded)?;
+            return Ok(());
+        }
+
+        Self::pay_from_reserve(vault, boost_assets)?;
+        position.shares = checked_add(position.shares, boost_shares)?;
+        vault.total_shares = checked_add(vault.total_shares, boost_shares)?;
+        Ok(())
+    }
+
+    /// Mueve rendimiento de la reserva a total_assets hasta donde alcance
+    fn pay_from_reserve(vault: &mut VaultState, amount: i128) -> Result<(), Error> {
+        let funded = amount.min(vault.reserve);
+        vault.reserve -= funded;
+        vault.total_assets = checked_add(vault.total_assets, funded)?;
+        vault.unfunded_yield = checked_add(vault.unfunded_yield, amount - funded)?;
+        Ok(())
+    }
+
+    /// Valor bloqueado: shares flexibles + depósitos a plazo (principal e interés)
+    fn tvl(vault: &VaultState) -> i128 {
+        vault.total_assets.saturating_add(vault.term_liabilities)
+    }
+
+    /// Principal del usuario entre la posición flexible y sus plazos
+    fn user_principal(env: &Env, user: &Address, flexible_principal: i128) -> i128 {
+        Self::get_terms(env, user)
+            .iter()
+            .fold(flexible_principal, |acc, term| acc.saturating_add(term.principal))
+    }
+
+    fn assert_within_caps(
+        env: &Env,
+        vault: &VaultState,
+        user: &Address,
+        flexible_principal: i128,
+        level: u32,
+        amount: i128,
+    ) -> Result<(), Error> {
+        let principal = Self::user_principal(env, user, flexible_principal);
+        if checked_add(principal, amount)? > Self::get_user_cap(env.clone(), level) {
+            return Err(Error::ExceedsUserCap);
+        }
+        if checked_add(Self::tvl(vault), amount)? > Self::get_tvl_cap(env.clone()) {
+            return Err(Error::ExceedsTvlCap);
+        }
+        Ok(())
+    }
+
+    /// Registra al referidor en el primer depósito del usuario
+    fn register_referral(env: &Env, user: &Address, referrer: Address) -> Result<(), Error> {
+        if &referrer == user {
+            return Err(Error::InvalidReferral);
+        }
+        if env.storage().persistent().has(&Self::referral_key(user)) {
+            return Err(Error::AlreadyReferred);
+        }
+        if Self::get_position(env.clone(), user.clone()).is_some() {
+            return Err(Error::InvalidReferral);
+        }
+        let mut referrals = Self::referrals_of(env.clone(), referrer.clone());
+        if referrals.len() >= MAX_REFERRALS {
+            return Err(Error::TooManyReferrals);
+        }
+
+        let referral = Referral {
+            referrer: referrer.clone(),
+            referee: user.clone(),
+            qualified_since: None,
+            rewarded: false,
+        };
+        env.storage()
+            .persistent()
+            .set(&Self::referral_key(user), &referral);
+
+        referrals.push_back(user.clone());
+        env.storage()
+            .persistent()
+            .set(&Self::referrals_of_key(&referrer), &referrals);
+
+        env.events()
+            .publish((symbol_short!("referred"), referrer, user.clone()), ());
+
+        Ok(())
+    }
+
+    /// Marca desde cuándo el referido mantiene el balance mínimo, o lo reinicia
+    /// si bajó del mínimo
+    fn track_referral(env: &Env, user: &Address, balance: i128) {
+        let Some(mut referral) = Self::get_referral(env, user) else {
+            return;
+        };
+        if referral.rewarded {
+            return;
+        }
+
+        let config = Self::get_referral_config(env.clone());
+        referral.qualified_since = if balance >= config.min_balance {
+            referral
+                .qualified_since
+                .or(Some(env.ledger().timestamp()))
+        } else {
+            None
+        };
+        env.storage()
+            .persistent()
+            .set(&Self::referral_key(user), &referral);
+    }
+
+    fn referral_matured(env: &Env, referral: &Referral, config: &ReferralConfig) -> bool {
+        match referral.qualified_since {
+            Some(since) => {
+                env.ledger().timestamp().saturating_sub(since)
+                    >= config.min_days as u64 * SECONDS_PER_DAY
+            }
+            None => false,
+        }
+    }
+
+    fn get_referral(env: &Env, referee: &Address) -> Option<Referral> {
+        env.storage().persistent().get(&Self::referral_key(referee))
+    }
+
+    fn pause_count(env: &Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&Self::pause_count_key())
+            .unwrap_or(0)
+    }
+
+    fn pause_window(env: &Env, index: u32) -> PauseWindow {
+        env.storage()
+            .persistent()
+            .get(&Self::pause_window_key(index))
+            .unwrap_or_else(|| env.panic_with_error(Error::NotPaused))
+    }
+
+    /// Índice de la primera pausa que termina después de `timestamp`
+    /// (búsqueda binaria: las ventanas están ordenadas y no se traslapan)
+    fn first_pause_ending_after(env: &Env, timestamp: u64, count: u32) -> u32 {
+        let (mut low, mut high) = (0, count);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if Self::pause_window(env, mid).end <= timestamp {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        low
+    }
+
+    fn assert_not_paused(env: &Env) -> Result<(), Error> {
+        if Self::is_paused(env.clone()) {
+            return Err(Error::Paused);
+        }
+        Ok(())
+    }
+
+    /// El guardián o el admin pueden pausar y reanudar
+    fn assert_guardian(env: &Env, caller: &Address) {
+        caller.require_auth();
+        let guardian: Option<Address> = env.storage().instance().get(&Self::guardian_key());
+        if guardian.as_ref() == Some(caller) {
+            return;
+        }
+        if Self::get_admin(env.clone()).as_ref() != Some(caller) {
+            env.panic_with_error(Error::NotAuthorized);
+        }
+    }
+
+    fn term_bonus(term_days: u32) -> Option<i64> {
+        match term_days {
+            30 => Some(TERM_30_BONUS),
+            90 => Some(TERM_90_BONUS),
+            180 => Some(TERM_180_BONUS),
+            _ => None,
+        }
+    }
+
+    fn get_terms(env: &Env, user: &Address) -> Vec<TermPosition> {
+        env.storage()
+            .persistent()
+            .get(&Self::terms_key(user))
+            .unwrap_or(Vec::new(env))
+    }
+
+    fn save_terms(env: &Env, user: &Address, terms: &Vec<TermPosition>) {
+        if terms.is_empty() {
+            env.storage().persistent().remove(&Self::terms_key(user));
+        } else {
+            env.storage().persistent().set(&Self::terms_key(user), terms);
+        }
+    }
+
+    fn find_term(terms: &Vec<TermPosition>, term_id: u32) -> Option<u32> {
+        terms.iter().position(|term| term.id == term_id).map(|i| i as u32)
+    }
+
+    /// Transfiere `amount` al usuario, trayendo de las estrategias si hace falta.
+    /// `vault` ya debe reflejar el pago (plazos y reserva descontados).
+    fn pay_out(
+        env: &Env,
+        asset: &Address,
+        vault: &VaultState,
+        user: &Address,
+        amount: i128,
+    ) -> Result<(), Error> {
+        let token = token::Client::new(env, asset);
+        Self::ensure_liquidity(env, &token, vault, amount)?;
+        token.transfer(&env.current_contract_address(), user, &amount);
+        Ok(())
+    }
+
+    /// Verifica que tras pagar `amount` queden en el vault los depósitos a plazo
+    /// y la reserva, que siempre se mantienen líquidos; trae de las estrategias
+    /// lo que falte y falla con InsufficientLiquidity si no alcanza.
+    fn ensure_liquidity(
+        env: &Env,
+        token: &token::Client,
+        vault: &VaultState,
+        amount: i128,
+    ) -> Result<(), Error> {
+        let required = checked_add(
+            amount,
+            checked_add(vault.term_liabilities, vault.reserve)?,
+        )?;
+        let idle = token.balance(&env.current_contract_address());
+        if idle < required {
+            Self::pull_from_strategies(env, token, required - idle);
+        }
+        if token.balance(&env.current_contract_address()) < required {
+            return Err(Error::InsufficientLiquidity);
+        }
+        Ok(())
+    }
+
+    /// Suma de los balances de todas las estrategias
+    fn deployed_assets(env: &Env) -> i128 {
+        Self::get_allocations(env.clone())
+            .iter()
+            .map(|allocation| {
+                match StrategyClient::new(env, &allocation.strategy).try_balance() {
+                    Ok(Ok(balance)) => balance,
+                    _ => 0,
+                }
+            })
+            .fold(0i128, |acc, balance| acc.saturating_add(balance))
+    }
+
+    /// Retira hasta `amount` de las estrategias, en orden, hacia el vault.
+    /// Una estrategia que falla se salta; lo recibido se mide con el balance
+    /// del vault, no con lo que la estrategia reporta.
+    fn pull_from_strategies(env: &Env, token: &token::Client, amount: i128) {
+        let this = env.current_contract_address();
+        let mut remaining = amount;
+        for allocation in Self::get_allocations(env.clone()).iter() {
+            if remaining <= 0 {
+                break;
+            }
+            let client = StrategyClient::new(env, &allocation.strategy);
+            let take = match client.try_balance() {
+                Ok(Ok(balance)) => balance.min(remaining),
+                _ => continue,
+            };
+            if take <= 0 {
+                continue;
+            }
+            let before = token.balance(&this);
+            if let Ok(Ok(_)) = client.try_withdraw(&take) {
+                remaining -= token.balance(&this) - before;
+            }
+        }
+    }
+
+    /// Ajusta la contabilidad a lo que el vault realmente custodia. Si los tokens
+    /// ociosos más lo que reportan las estrategias no cubren total_assets, los
+    /// depósitos a plazo y la reserva, la pérdida se absorbe primero con la
+    /// reserva y luego reduce total_assets (baja el precio por share).
+    /// Si alguna estrategia no responde no se ajusta nada. Retorna la pérdida.
+    fn reconcile_strategies(
+        env: &Env,
+        token: &token::Client,
+        vault: &mut VaultState,
+    ) -> Result<i128, Error> {
+        let mut held = token.balance(&env.current_contract_address());
+        for allocation in Self::get_allocations(env.clone()).iter() {
+            match StrategyClient::new(env, &allocation.strategy).try_balance() {
+                Ok(Ok(balance)) => held = checked_add(held, balance)?,
+                _ => return Ok(0),
+            }
+        }
+
+        let expected = checked_add(
+            vault.total_assets,
+            checked_add(vault.term_liabilities, vault.reserve)?,
+        )?;
+        let loss = expected - held;
+        if loss <= 0 {
+            return Ok(0);
+        }
+
+        let from_reserve = loss.min(vault.reserve);
+        vault.reserve -= from_reserve;
+        vault.total_assets = (vault.total_assets - (loss - from_reserve)).max(0);
+
+        env.events().publish((symbol_short!("loss"),), loss);
+
+        Ok(loss)
+    }
+
+    /// Cosecha el rendimiento de las estrategias hacia `vault.reserve`.
+    /// Una estrategia que falla se salta; cuenta lo que realmente llegó.
+    fn harvest_strategies(env: &Env, vault: &mut VaultState) -> Result<i128, Error> {
+        let token = token::Client::new(env, &Self::get_asset(env)?);
+        let this = env.current_contract_address();
+        let before = token.balance(&this);
+        for allocation in Self::get_allocations(env.clone()).iter() {
+            let _ = StrategyClient::new(env, &allocation.strategy).try_harvest();
+        }
+        let harvested = token.balance(&this) - before;
+        vault.reserve = checked_add(vault.reserve, harvested)?;
+        Ok(harvested)
+    }
+
+    fn get_asset(env: &Env) -> Result<Address, Error> {
+        env.storage()
+            .instance()
+            .get(&Self::asset_key())
+            .ok_or(Error::AssetNotConfigured)
+    }
+
+    /// Consulta el nivel en user-levels con el cliente tipado. Un usuario sin
+    /// nivel registrado es Bronze; cualquier otra falla se reporta como error.
+    fn fetch_user_level(env: &Env, user: &Address) -> Result<u32, Error> {
+        let user_levels: Address = env
+            .storage()
+            .instance()
+            .get(&Self::user_levels_key())
+            .ok_or(Error::UserLevelsNotConfigured)?;
+
+        match UserLevelsClient::new(env, &user_levels).try_get_user_level(user) {
+            Ok(Ok(Some(data))) => Ok(data.level),
+            Ok(Ok(None)) => Ok(1), // Bronze: sin nivel registrado
+            _ => Err(Error::UserLevelsCallFailed),
+        }
+    }
+
+    /// Actualiza el nivel cacheado en la posición si venció el intervalo de refresco
+    fn refresh_level(env: &Env, position: &mut SavingsPosition, force: bool) -> Result<(), Error> {
+        let now = env.ledger().timestamp();
+        let interval = Self::get_level_refresh_interval(env.clone());
+        if !force && now.saturating_sub(position.level_checked_at) < interval {
+            return Ok(());
+        }
+
+        let level = Self::fetch_user_level(env, &position.user)?;
+        position.level = level;
+        position.apy = Self::get_apy_for_level(env.clone(), level);
+        position.level_checked_at = now;
+        Ok(())
+    }
+
+    fn assert_verified_student(env: &Env, user: &Address) -> Result<(), Error> {
+        let student_registry: Option<Address> = env
+            .storage()
+            .instance()
+            .get(&Self::student_registry_key());
+
+        let Some(student_registry) = student_registry else {
+            return Ok(());
+        };
+
+        let mut args = Vec::new(env);
+        args.push_back(user.clone().into_val(env));
+
+        let verified: bool = env.invoke_contract(
+            &student_registry,
+            &Symbol::new(env, "is_verified_student"),
+            args,
+        );
+
+        if !verified {
+            return Err(Error::NotVerifiedStudent);
+        }
+        Ok(())
+    }
+
+    fn default_apy(level: u32) -> i64 {
+        match level {
+            1 => BRONZE_APY,   // Bronze: 2%
+            2 => SILVER_APY,   // Silver: 4%
+            3 => GOLD_APY,     // Gold: 6%
+            4 => PLATINUM_APY, // Platinum: 8%
+            _ => BRONZE_APY,
+        }
+    }
+
+    fn apy_schedule(env: &Env, level: u32) -> Vec<RateChange> {
+        env.storage()
+            .instance()
+            .get(&Self::apy_schedule_key(level))
+            .unwrap_or(Vec::new(env))
+    }
+
+    /// APY de un nivel vigente en `timestamp`, limitado por el máximo
+    fn apy_at(env: &Env, level: u32, timestamp: u64) -> i64 {
+        let mut apy = Self::default_apy(level);
+        for change in Self::apy_schedule(env, level).iter() {
+            if change.effective_at > timestamp {
+                break;
+            }
+            apy = change.apy;
+        }
+        apy.min(Self::get_max_apy(env.clone()))
+    }
+
+    /// Factor de crecimiento (WAD) de un nivel entre `from` y `to`. El intervalo se
+    /// divide en cada cambio de tasa para que cada tramo use la tasa vigente;
+    /// los tramos dentro de una pausa no generan rendimiento.
+    fn growth_between(env: &Env, level: u32, from: u64, to: u64) -> Result<i128, Error> {
+        let mode = Self::get_compounding_mode(env.clone());
+        let max_apy = Self::get_max_apy(env.clone());
+        let schedule = Self::apy_schedule(env, level);
+
+        // Solo se leen las pausas que se traslapan con [from, to)
+        let pause_count = Self::pause_count(env);
+        let mut pause_index = Self::first_pause_ending_after(env, from, pause_count);
+        let mut window = (pause_index < pause_count).then(|| Self::pause_window(env, pause_index));
+
+        let mut factor = WAD;
+        let mut t = from;
+        while t < to {
+            let mut apy = Self::default_apy(level);
+            let mut next = to;
+            for change in schedule.iter() {
+                if change.effective_at <= t {
+                    apy = change.apy;
+                } else {
+                    next = next.min(change.effective_at);
+                    break;
+                }
+            }
+
+            while window.as_ref().is_some_and(|w| w.end <= t) {
+                pause_index += 1;
+                window = (pause_index < pause_count).then(|| Self::pause_window(env, pause_index));
+            }
+
+            let mut paused = false;
+            if let Some(w) = &window {
+                if w.start <= t {
+                    paused = true;
+                    next = next.min(w.end);
+                } else {
+                    next = next.min(w.start);
+                }
+            }
+
+            if !paused {
+                factor = mul_div(factor, growth_factor(mode, apy.min(max_apy), next - t)?, WAD)?.0;
+            }
+            t = next;
+        }
+        Ok(factor)
+    }
+
+    fn position_key(user: &Address) -> (Symbol, Address) {
+        (symbol_short!("pos"), user.clone())
+    }
+
+    fn vault_key() -> Symbol {
+        symbol_short!("vault")
+    }
+
+    fn asset_key() -> Symbol {
+        symbol_short!("asset")
+    }
+
+    fn compounding_key() -> Symbol {
+        symbol_short!("compound")
+    }
+
+    fn allocations_key() -> Symbol {
+        symbol_short!("strats")
+    }
+
+    fn apy_schedule_key(level: u32) -> (Symbol, u32) {
+        (symbol_short!("apy"), level)
+    }
+
+    fn max_apy_key() -> Symbol {
+        symbol_short!("max_apy")
+    }
+
+    fn user_cap_key(level: u32) -> (Symbol, u32) {
+        (symbol_short!("user_cap"), level)
+    }
+
+    fn tvl_cap_key() -> Symbol {
+        symbol_short!("tvl_cap")
+    }
+
+    fn referral_config_key() -> Symbol {
+        symbol_short!("ref_cfg")
+    }
+
+    fn referral_key(referee: &Address) -> (Symbol, Address) {
+        (symbol_short!("ref"), referee.clone())
+    }
+
+    fn referrals_of_key(referrer: &Address) -> (Symbol, Address) {
+        (symbol_short!("refs_of"), referrer.clone())
+    }
+
+    fn guardian_key() -> Symbol {
+        symbol_short!("guardian")
+    }
+
+    fn pause_count_key() -> Symbol {
+        symbol_short!("pause_cnt")
+    }
+
+    fn pause_window_key(index: u32) -> (Symbol, u32) {
+        (symbol_short!("pause"), index)
+    }
+
+    fn terms_key(user: &Address) -> (Symbol, Address) {
+        (symbol_short!("terms"), user.clone())
+    }
+
+    fn term_seq_key() -> Symbol {
+        symbol_short!("term_seq")
+    }
+
+    fn user_levels_key() -> Symbol {
+        symbol_short!("usr_lvl")
+    }
+
+    fn level_refresh_key() -> Symbol {
+        symbol_short!("lvl_rfsh")
+    }
+
+    fn student_registry_key() -> Symbol {
+        symbol_short!("stdnt_reg")
+    }
+
+    fn admin_key() -> Symbol {
+        symbol_short!("admin")
+    }
+
+    /// Exige la firma del admin guardado en `initialize` y lo retorna
+    fn assert_admin(env: &Env) -> Address {
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&Self::admin_key())
+            .unwrap_or_else(|| env.panic_with_error(Error::NotAuthorized));
+        admin.require_auth();
+        admin
+    }
 }
 
+mod test;
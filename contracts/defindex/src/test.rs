@@ -0,0 +1,699 @@
+#![cfg(test)]
+extern crate std;
+
+use super::*;
+use mock_strategy::{Error as StrategyError, FixedRateStrategy, FixedRateStrategyClient};
+use soroban_sdk::{
+    contract, contracterror, contractimpl,
+    testutils::{Address as _, Ledger},
+    token::StellarAssetClient,
+    xdr::ToXdr,
+    Bytes, BytesN,
+};
+use student_registry::{StudentRegistry, StudentRegistryClient};
+use user_levels::{UserLevels, UserLevelsClient as LevelsClient};
+
+struct Setup<'a> {
+    env: Env,
+    vault: DeFindexClient<'a>,
+    levels: LevelsClient<'a>,
+    asset: Address,
+}
+
+impl Setup<'_> {
+    fn new() -> Self {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let admin = Address::generate(&env);
+        let asset = env
+            .register_stellar_asset_contract_v2(admin.clone())
+            .address();
+
+        let levels = LevelsClient::new(&env, &env.register(UserLevels, ()));
+        levels.initialize(&admin);
+        levels.set_contracts(&Address::generate(&env), &Address::generate(&env));
+
+        let vault = DeFindexClient::new(&env, &env.register(DeFindex, ()));
+        vault.initialize(&admin);
+        vault.set_asset(&asset);
+        vault.set_user_levels(&levels.address);
+
+        Setup {
+            env,
+            vault,
+            levels,
+            asset,
+        }
+    }
+
+    fn user_with(&self, amount: i128) -> Address {
+        let user = Address::generate(&self.env);
+        StellarAssetClient::new(&self.env, &self.asset).mint(&user, &amount);
+        user
+    }
+
+    fn fund_reserve(&self, amount: i128) {
+        let admin = self.vault.get_admin().unwrap();
+        StellarAssetClient::new(&self.env, &self.asset).mint(&admin, &amount);
+        self.vault.fund_reserve(&amount);
+    }
+
+    /// Estrategia de tasa fija (`apy` en bps) ligada a este vault
+    fn strategy(&self, apy: i64) -> FixedRateStrategyClient<'_> {
+        let strategy =
+            FixedRateStrategyClient::new(&self.env, &self.env.register(FixedRateStrategy, ()));
+        strategy.initialize(
+            &self.vault.get_admin().unwrap(),
+            &self.vault.address,
+            &self.asset,
+            &apy,
+        );
+        strategy
+    }
+
+    fn advance(&self, seconds: u64) {
+        self.env
+            .ledger()
+            .with_mut(|ledger| ledger.timestamp += seconds);
+    }
+}
+
+#[contracterror]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MockVerifierError {
+    ProofAlreadyUsed = 5,
+    PublicInputMismatch = 9,
+}
+
+/// Verificador mínimo para desbloquear logros en user-levels
+#[contract]
+pub struct MockVerifier;
+
+#[contractimpl]
+impl MockVerifier {
+    pub fn consume_proof(
+        env: Env,
+        caller: Address,
+        _circuit_id: Option<Symbol>,
+        proof_blob: Bytes,
+        _context: BytesN<32>,
+        expected: Vec<BytesN<32>>,
+        wildcards: Vec<u32>,
+    ) -> Result<user_levels::VerificationResult, MockVerifierError> {
+        caller.require_auth();
+        let proof_id: BytesN<32> = env.crypto().keccak256(&proof_blob).into();
+        if env.storage().persistent().has(&proof_id) {
+            return Err(MockVerifierError::ProofAlreadyUsed);
+        }
+
+        // Blob [count][public inputs][proof], comparado como en los verificadores
+        let count = if proof_blob.len() >= 4 {
+            let mut header = [0u8; 4];
+            proof_blob.slice(0..4).copy_into_slice(&mut header);
+            u32::from_be_bytes(header)
+        } else {
+            0
+        };
+        if count != expected.len() || proof_blob.len() < 4 + count * 32 {
+            return Err(MockVerifierError::PublicInputMismatch);
+        }
+        let mut public_inputs = Vec::new(&env);
+        for i in 0..count {
+            let mut field = [0u8; 32];
+            proof_blob
+                .slice(4 + i * 32..4 + (i + 1) * 32)
+                .copy_into_slice(&mut field);
+            let input = BytesN::from_array(&env, &field);
+            if !wildcards.contains(i) && input != expected.get_unchecked(i) {
+                return Err(MockVerifierError::PublicInputMismatch);
+            }
+            public_inputs.push_back(input);
+        }
+
+        env.storage().persistent().set(&proof_id, &true);
+        Ok(user_levels::VerificationResult {
+            proof_id,
+            public_inputs,
+        })
+    }
+}
+
+/// Sube a `user` a Gold en user-levels con un logro de tier 3
+fn make_gold(setup: &Setup, user: &Address) {
+    let env = &setup.env;
+    setup
+        .levels
+        .set_achievement_verifier(&env.register(MockVerifier, ()));
+    setup.levels.define_achievement(&1, &0, &0, &0, &0);
+
+    // Public inputs: mínimos, hash del usuario y tier
+    let mut user_hash = env.crypto().sha256(&user.clone().to_xdr(env)).to_array();
+    user_hash[0] = 0;
+    let mut blob = Bytes::from_array(env, &6u32.to_be_bytes());
+    for input in [1u64, 1, 1, 1] {
+        blob.extend_from_array(&[0u8; 24]);
+        blob.extend_from_array(&input.to_be_bytes());
+    }
+    blob.extend_from_array(&user_hash);
+    blob.extend_from_array(&[0u8; 24]);
+    blob.extend_from_array(&3u64.to_be_bytes());
+    blob.extend_from_array(&[0u8; 100]);
+    setup.levels.submit_achievement(user, &1, &blob);
+}
+
+#[test]
+fn deposit_reads_level_from_deployed_user_levels() {
+    let setup = Setup::new();
+    let gold = setup.user_with(1_000 * UNIT);
+    let bronze = setup.user_with(1_000 * UNIT);
+    make_gold(&setup, &gold);
+
+    setup.vault.deposit(&gold, &(100 * UNIT), &None);
+    setup.vault.deposit(&bronze, &(100 * UNIT), &None);
+
+    // El layout de UserLevelData coincide entre ambos contratos
+    let gold_position = setup.vault.get_position(&gold).unwrap();
+    assert_eq!(gold_position.level, 3);
+    assert_eq!(gold_position.apy, GOLD_APY);
+
+    // Sin nivel registrado en user-levels el usuario es Bronze
+    assert!(setup.levels.get_user_level(&bronze).is_none());
+    assert_eq!(setup.vault.get_position(&bronze).unwrap().level, 1);
+}
+
+#[test]
+fn student_gating_accepts_allowlisted_contracts() {
+    let setup = Setup::new();
+    let env = &setup.env;
+    let admin = setup.vault.get_admin().unwrap();
+
+    let registry = StudentRegistryClient::new(env, &env.register(StudentRegistry, ()));
+    registry.initialize(&admin);
+    setup.vault.set_student_registry(&registry.address);
+
+    // Sin verificación no se puede depositar
+    let user = setup.user_with(100 * UNIT);
+    assert_eq!(
+        setup.vault.try_deposit(&user, &(100 * UNIT), &None),
+        Err(Ok(Error::NotVerifiedStudent))
+    );
+
+    // Un contrato autorizado (p. ej. savings-goals) deposita en nombre de estudiantes
+    let savings_goals = setup.user_with(100 * UNIT);
+    assert_eq!(
+        setup.vault.try_deposit(&savings_goals, &(100 * UNIT), &None),
+        Err(Ok(Error::NotVerifiedStudent))
+    );
+    registry.set_allowed_contract(&admin, &savings_goals, &true);
+    setup.vault.deposit(&savings_goals, &(100 * UNIT), &None);
+    assert_eq!(
+        setup.vault.get_position(&savings_goals).unwrap().principal,
+        100 * UNIT
+    );
+}
+
+#[test]
+fn gold_position_earns_level_boost() {
+    let setup = Setup::new();
+    setup.fund_reserve(1_000 * UNIT);
+    let gold = setup.user_with(1_000 * UNIT);
+    let bronze = setup.user_with(1_000 * UNIT);
+    make_gold(&setup, &gold);
+
+    setup.vault.deposit(&gold, &(1_000 * UNIT), &None);
+    setup.vault.deposit(&bronze, &(1_000 * UNIT), &None);
+    setup.advance(SECONDS_PER_YEAR as u64);
+
+    // Bronze: 2% base. Gold: el boost (6% - 2%) se calcula sobre el valor ya
+    // acumulado al cerrar el periodo: 1_020 * 4% = 40.8
+    let bronze_balance = setup.vault.get_balance(&bronze);
+    let gold_balance = setup.vault.get_balance(&gold);
+    assert!((bronze_balance - 1_020 * UNIT).abs() <= 1);
+    assert!((gold_balance - 10_608 * UNIT / 10).abs() <= 1);
+}
+
+/// Generador xorshift con semilla fija para las pruebas de propiedades
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, bound: u64) -> u64 {
+        self.next() % bound
+    }
+}
+
+/// APY máximo razonable para las propiedades (20%)
+const MAX_APY: i64 = 2_000;
+
+const MODES: [CompoundingMode; 4] = [
+    CompoundingMode::Simple,
+    CompoundingMode::Continuous,
+    CompoundingMode::Daily,
+    CompoundingMode::PerLedger,
+];
+
+#[test]
+fn prop_mul_div_matches_wide_division() {
+    let mut rng = Rng(0x9E37_79B9_7F4A_7C15);
+    for _ in 0..2_000 {
+        // Operandos de hasta ~2^84 y ~2^64: parte de los productos no cabe en i128
+        let a = rng.next() as i128 * rng.below(1 << 20) as i128;
+        let b = rng.next() as i128;
+        let denominator = rng.below(u64::MAX - 1) as i128 + 1;
+
+        match a.checked_mul(b) {
+            Some(product) => {
+                let (quotient, remainder) = mul_div(a, b, denominator).unwrap();
+                assert_eq!(quotient * denominator + remainder, product);
+                assert!(remainder >= 0 && remainder < denominator);
+            }
+            None => assert_eq!(mul_div(a, b, denominator), Err(Error::ArithmeticOverflow)),
+        }
+    }
+
+    // Un producto fuera de rango es un error, no un pánico
+    assert_eq!(mul_div(i128::MAX, 2, 1), Err(Error::ArithmeticOverflow));
+    assert_eq!(mul_div(i128::MIN, -1, 1), Err(Error::ArithmeticOverflow));
+}
+
+#[test]
+fn prop_growth_is_monotonic_and_at_least_one() {
+    let mut rng = Rng(0xD1B5_4A32_D192_ED03);
+    for _ in 0..300 {
+        let apy = rng.below(MAX_APY as u64 + 1) as i64;
+        let elapsed = rng.below(5 * SECONDS_PER_YEAR as u64);
+        let later = elapsed + rng.below(30 * SECONDS_PER_DAY);
+
+        for mode in MODES {
+            let now = growth_factor(mode, apy, elapsed).unwrap();
+            assert!(now >= WAD, "{:?} apy={} t={}", mode, apy, elapsed);
+            assert!(growth_factor(mode, apy, later).unwrap() >= now);
+            assert!(growth_factor(mode, apy + 1, elapsed).unwrap() >= now);
+        }
+    }
+}
+
+#[test]
+fn prop_compounding_modes_are_ordered() {
+    // Simple <= Daily <= PerLedger <= Continuous (salvo redondeo de la serie)
+    let mut rng = Rng(0x2545_F491_4F6C_DD1D);
+    let tolerance = WAD / 1_000_000_000;
+    for _ in 0..200 {
+        let apy = rng.below(MAX_APY as u64 + 1) as i64;
+        let elapsed = rng.below(3 * SECONDS_PER_YEAR as u64);
+
+        let simple = growth_factor(CompoundingMode::Simple, apy, elapsed).unwrap();
+        let daily = growth_factor(CompoundingMode::Daily, apy, elapsed).unwrap();
+        let ledger = growth_factor(CompoundingMode::PerLedger, apy, elapsed).unwrap();
+        let continuous = growth_factor(CompoundingMode::Continuous, apy, elapsed).unwrap();
+
+        assert!(simple <= daily + tolerance);
+        assert!(daily <= ledger + tolerance);
+        assert!(ledger <= continuous + tolerance);
+    }
+}
+
+#[test]
+fn prop_continuous_growth_splits_multiplicatively() {
+    let mut rng = Rng(0x8CB9_2BA7_2F3D_8DD7);
+    let tolerance = WAD / 1_000_000_000;
+    for _ in 0..200 {
+        let apy = rng.below(MAX_APY as u64 + 1) as i64;
+        let first = rng.below(2 * SECONDS_PER_YEAR as u64);
+        let second = rng.below(2 * SECONDS_PER_YEAR as u64);
+
+        let whole = growth_factor(CompoundingMode::Continuous, apy, first + second).unwrap();
+        let split = mul_div(
+            growth_factor(CompoundingMode::Continuous, apy, first).unwrap(),
+            growth_factor(CompoundingMode::Continuous, apy, second).unwrap(),
+            WAD,
+        )
+        .unwrap()
+        .0;
+        assert!((whole - split).abs() <= tolerance, "{} vs {}", whole, split);
+    }
+}
+
+#[test]
+fn prop_extreme_inputs_error_instead_of_panicking() {
+    let mut rng = Rng(0x5851_F42D_4C95_7F2D);
+    for _ in 0..200 {
+        let apy = (rng.next() >> 1) as i64;
+        let elapsed = rng.next();
+        for mode in MODES {
+            match growth_factor(mode, apy, elapsed) {
+                Ok(factor) => assert!(factor >= WAD),
+                Err(err) => assert_eq!(err, Error::ArithmeticOverflow),
+            }
+        }
+    }
+}
+
+#[test]
+fn project_balance_uses_current_level_from_user_levels() {
+    let setup = Setup::new();
+    setup.fund_reserve(1_000 * UNIT);
+    let user = setup.user_with(1_000 * UNIT);
+    setup.vault.deposit(&user, &(1_000 * UNIT), &None);
+    assert_eq!(setup.vault.get_position(&user).unwrap().level, 1);
+
+    // Sube a Gold sin tocar el vault: el nivel cacheado sigue en Bronze
+    make_gold(&setup, &user);
+    assert_eq!(setup.vault.get_position(&user).unwrap().level, 1);
+
+    let in_a_year = setup.env.ledger().timestamp() + SECONDS_PER_YEAR as u64;
+    let projected = setup.vault.project_balance(&user, &in_a_year);
+    assert_eq!(projected, 1_060 * UNIT);
+}
+
+#[test]
+fn set_allocation_rejects_overflowing_weights() {
+    let setup = Setup::new();
+    let first = Address::generate(&setup.env);
+    let second = Address::generate(&setup.env);
+    setup.vault.set_allocation(&first, &5_000);
+
+    assert_eq!(
+        setup.vault.try_set_allocation(&second, &(u32::MAX - 1_000)),
+        Err(Ok(Error::InvalidAllocation))
+    );
+    assert_eq!(setup.vault.get_allocations().len(), 1);
+}
+
+#[test]
+fn unbacked_strategy_yield_is_not_withdrawable() {
+    let setup = Setup::new();
+    let strategy = setup.strategy(1_000);
+    let user = setup.user_with(1_000 * UNIT);
+    setup.vault.deposit(&user, &(1_000 * UNIT), &None);
+    setup.vault.set_allocation(&strategy.address, &10_000);
+    setup.vault.rebalance();
+    assert_eq!(strategy.balance(), 1_000 * UNIT);
+
+    // Un año al 10% sin que nadie fondee la estrategia: el rendimiento no cuenta
+    setup.advance(SECONDS_PER_YEAR as u64);
+    assert_eq!(strategy.balance(), 1_000 * UNIT);
+    setup.env.as_contract(&setup.vault.address, || {
+        assert_eq!(
+            strategy.try_withdraw(&(1_100 * UNIT)),
+            Err(Ok(StrategyError::InsufficientBalance))
+        );
+    });
+
+    // Pedir más de lo que hay falla con error, sin abortar por la transferencia
+    let balance = setup.vault.get_balance(&user);
+    assert!(balance >= 1_000 * UNIT);
+    if balance > 1_000 * UNIT {
+        assert_eq!(
+            setup.vault.try_withdraw(&user, &balance),
+            Err(Ok(Error::InsufficientLiquidity))
+        );
+    }
+    setup.vault.withdraw(&user, &(1_000 * UNIT));
+    assert_eq!(strategy.balance(), 0);
+}
+
+#[test]
+fn rebalance_harvests_strategy_yield_into_reserve() {
+    let setup = Setup::new();
+    setup.fund_reserve(100 * UNIT);
+    let strategy = setup.strategy(1_000);
+    let user = setup.user_with(1_000 * UNIT);
+    setup.vault.deposit(&user, &(1_000 * UNIT), &None);
+    setup.vault.set_allocation(&strategy.address, &10_000);
+    setup.vault.rebalance();
+
+    // Solo 40 de los 100 acumulados están respaldados por tokens
+    setup.advance(SECONDS_PER_YEAR as u64);
+    StellarAssetClient::new(&setup.env, &setup.asset).mint(&strategy.address, &(40 * UNIT));
+    assert_eq!(strategy.balance(), 1_040 * UNIT);
+
+    let reserve_before = setup.vault.solvency().reserve;
+    setup.vault.rebalance();
+
+    // El rendimiento cosechado queda contabilizado en la reserva, no ocioso
+    assert_eq!(setup.vault.solvency().reserve, reserve_before + 40 * UNIT);
+    let idle = token::Client::new(&setup.env, &setup.asset).balance(&setup.vault.address);
+    assert!(idle >= setup.vault.solvency().reserve);
+    assert!(setup.vault.solvency().solvent);
+
+    let balance = setup.vault.get_balance(&user);
+    setup.vault.withdraw(&user, &balance);
+    assert!(setup.vault.solvency().solvent);
+}
+
+/// Estrategia de prueba que reporta el balance que fije la prueba
+/// y puede rechazar depósitos
+#[contract]
+pub struct TestStrategy;
+
+#[contractimpl]
+impl TestStrategy {
+    pub fn set_state(env: Env, balance: i128, reject_deposits: bool) {
+        env.storage().instance().set(&symbol_short!("bal"), &balance);
+        env.storage().instance().set(&symbol_short!("reject"), &reject_deposits);
+    }
+
+    pub fn deposit(env: Env, amount: i128) {
+        if env.storage().instance().get(&symbol_short!("reject")).unwrap_or(false) {
+            panic!("deposit rejected");
+        }
+        let balance = Self::balance(env.clone()) + amount;
+        env.storage().instance().set(&symbol_short!("bal"), &balance);
+    }
+
+    pub fn withdraw(_env: Env, _amount: i128) -> i128 {
+        panic!("not supported");
+    }
+
+    pub fn balance(env: Env) -> i128 {
+        env.storage().instance().get(&symbol_short!("bal")).unwrap_or(0)
+    }
+
+    pub fn harvest(_env: Env) -> i128 {
+        0
+    }
+}
+
+#[test]
+fn rebalance_reverts_when_strategy_rejects_deposit() {
+    let setup = Setup::new();
+    let strategy = TestStrategyClient::new(&setup.env, &setup.env.register(TestStrategy, ()));
+    strategy.set_state(&0, &true);
+    let user = setup.user_with(1_000 * UNIT);
+    setup.vault.deposit(&user, &(1_000 * UNIT), &None);
+    setup.vault.set_allocation(&strategy.address, &5_000);
+
+    assert_eq!(
+        setup.vault.try_rebalance(),
+        Err(Ok(Error::StrategyCallFailed))
+    );
+
+    // La transferencia se revierte: los tokens siguen en el vault
+    let token = token::Client::new(&setup.env, &setup.asset);
+    assert_eq!(token.balance(&setup.vault.address), 1_000 * UNIT);
+    assert_eq!(token.balance(&strategy.address), 0);
+    assert_eq!(setup.vault.total_assets(), 1_000 * UNIT);
+}
+
+#[test]
+fn rebalance_records_strategy_losses() {
+    let setup = Setup::new();
+    setup.fund_reserve(50 * UNIT);
+    let strategy = TestStrategyClient::new(&setup.env, &setup.env.register(TestStrategy, ()));
+    let user = setup.user_with(1_000 * UNIT);
+    setup.vault.deposit(&user, &(1_000 * UNIT), &None);
+    setup.vault.set_allocation(&strategy.address, &5_000);
+    setup.vault.rebalance();
+    assert_eq!(strategy.balance(), 500 * UNIT);
+    assert!(setup.vault.solvency().solvent);
+
+    // La estrategia pierde 200: la reserva absorbe 50 y total_assets baja 150
+    strategy.set_state(&(300 * UNIT), &false);
+    assert!(!setup.vault.solvency().solvent);
+    setup.vault.rebalance();
+
+    assert_eq!(setup.vault.reserve_balance(), 0);
+    assert_eq!(setup.vault.total_assets(), 850 * UNIT);
+    assert_eq!(setup.vault.get_balance(&user), 850 * UNIT);
+    assert!(setup.vault.solvency().solvent);
+}
+
+#[test]
+fn flexible_withdrawal_cannot_spend_term_liquidity() {
+    let setup = Setup::new();
+    setup.fund_reserve(100 * UNIT);
+    let strategy = setup.strategy(0);
+    let saver = setup.user_with(1_000 * UNIT);
+    setup.vault.deposit(&saver, &(1_000 * UNIT), &None);
+    let termer = setup.user_with(1_000 * UNIT);
+    let term_id = setup.vault.deposit_term(&termer, &(1_000 * UNIT), &30);
+
+    // El principal flexible va a la estrategia; plazos y reserva se quedan líquidos
+    setup.vault.set_allocation(&strategy.address, &10_000);
+    setup.vault.rebalance();
+    assert_eq!(strategy.balance(), 1_000 * UNIT);
+
+    // La estrategia pierde la mitad de los fondos y ya no puede devolver el principal
+    let token = token::Client::new(&setup.env, &setup.asset);
+    let sink = Address::generate(&setup.env);
+    token.transfer(&strategy.address, &sink, &(500 * UNIT));
+
+    // El retiro flexible no puede tomar los fondos del depósito a plazo
+    assert_eq!(
+        setup.vault.try_withdraw(&saver, &(600 * UNIT)),
+        Err(Ok(Error::InsufficientLiquidity))
+    );
+
+    setup.advance(30 * SECONDS_PER_DAY);
+    let payout = setup.vault.withdraw_term(&termer, &term_id);
+    assert!(payout > 1_000 * UNIT);
+    assert_eq!(token.balance(&termer), payout);
+}
+
+#[test]
+fn deposit_term_caps_open_terms_per_user() {
+    let setup = Setup::new();
+    setup.fund_reserve(100 * UNIT);
+    let user = setup.user_with(1_000 * UNIT);
+
+    for _ in 0..MAX_OPEN_TERMS {
+        setup.vault.deposit_term(&user, &(10 * UNIT), &30);
+    }
+    assert_eq!(
+        setup.vault.try_deposit_term(&user, &(10 * UNIT), &30),
+        Err(Ok(Error::TooManyTerms))
+    );
+
+    // Al cerrar uno se libera un lugar
+    setup.advance(30 * SECONDS_PER_DAY);
+    setup.vault.withdraw_term(&user, &1);
+    setup.vault.deposit_term(&user, &(10 * UNIT), &30);
+}
+
+#[test]
+fn redeem_burns_exact_shares() {
+    let setup = Setup::new();
+    let user = setup.user_with(1_000 * UNIT);
+    let shares = setup.vault.deposit(&user, &(1_000 * UNIT), &None);
+
+    assert_eq!(setup.vault.redeem(&user, &(shares / 4)), 250 * UNIT);
+    assert_eq!(
+        setup.vault.get_position(&user).unwrap().shares,
+        shares - shares / 4
+    );
+    assert_eq!(
+        setup.vault.try_redeem(&user, &shares),
+        Err(Ok(Error::InsufficientBalance))
+    );
+    assert_eq!(
+        setup.vault.try_redeem(&user, &0),
+        Err(Ok(Error::InvalidAmount))
+    );
+}
+
+#[test]
+fn set_level_apy_validates_rate() {
+    let setup = Setup::new();
+
+    assert_eq!(
+        setup.vault.try_set_level_apy(&3, &-1, &0),
+        Err(Ok(Error::InvalidAmount))
+    );
+    assert_eq!(
+        setup.vault.try_set_level_apy(&3, &(setup.vault.get_max_apy() + 1), &0),
+        Err(Ok(Error::ApyAboveMax))
+    );
+    assert_eq!(
+        setup.vault.try_set_level_apy(&5, &100, &0),
+        Err(Ok(Error::InvalidLevel))
+    );
+
+    setup.vault.set_level_apy(&3, &700, &0);
+    assert_eq!(setup.vault.get_apy_for_level(&3), 700);
+}
+
+#[test]
+fn apy_schedule_is_bounded() {
+    let setup = Setup::new();
+    let start = setup.env.ledger().timestamp();
+
+    // Todos los cambios pendientes se conservan hasta el tope
+    for i in 1..=MAX_APY_CHANGES as u64 {
+        setup.vault.set_level_apy(&2, &(i as i64 * 10), &(start + i * 100));
+    }
+    assert_eq!(
+        setup.vault.try_set_level_apy(&2, &500, &(start + 10_000)),
+        Err(Ok(Error::TooManyApyChanges))
+    );
+
+    // Una vez superados, los cambios más antiguos se descartan
+    setup.advance(MAX_APY_CHANGES as u64 * 100 + 1);
+    setup.vault.set_level_apy(&2, &500, &0);
+    let schedule = setup.vault.get_apy_schedule(&2);
+    assert_eq!(schedule.len(), MAX_APY_CHANGES);
+    assert_eq!(schedule.get_unchecked(0).effective_at, start + 200);
+    assert_eq!(setup.vault.get_apy_for_level(&2), 500);
+}
+
+#[test]
+fn emergency_withdraw_requires_pause() {
+    let setup = Setup::new();
+    setup.fund_reserve(100 * UNIT);
+    let user = setup.user_with(1_000 * UNIT);
+    setup.vault.deposit(&user, &(400 * UNIT), &None);
+    setup.vault.deposit_term(&user, &(600 * UNIT), &90);
+
+    // Fuera de pausa no se puede saltar el plazo ni la penalización
+    assert_eq!(
+        setup.vault.try_emergency_withdraw(&user),
+        Err(Ok(Error::NotPaused))
+    );
+
+    // En pausa se devuelve el principal; el interés del plazo regresa a la reserva
+    let admin = setup.vault.get_admin().unwrap();
+    setup.vault.pause(&admin);
+    let reserve_before = setup.vault.reserve_balance();
+    assert_eq!(setup.vault.emergency_withdraw(&user), 1_000 * UNIT);
+    assert_eq!(
+        token::Client::new(&setup.env, &setup.asset).balance(&user),
+        1_000 * UNIT
+    );
+    assert!(setup.vault.reserve_balance() > reserve_before);
+    let (position, terms) = setup.vault.list_positions(&user);
+    assert!(position.is_none() && terms.is_empty());
+}
+
+#[test]
+fn referrals_are_capped_per_referrer() {
+    let setup = Setup::new();
+    let referrer = setup.user_with(100 * UNIT);
+
+    let own = setup.user_with(10 * UNIT);
+    assert_eq!(
+        setup.vault.try_deposit(&own, &(10 * UNIT), &Some(own.clone())),
+        Err(Ok(Error::InvalidReferral))
+    );
+
+    for _ in 0..MAX_REFERRALS {
+        let referee = setup.user_with(10 * UNIT);
+        setup.vault.deposit(&referee, &(10 * UNIT), &Some(referrer.clone()));
+    }
+    assert_eq!(setup.vault.referrals_of(&referrer).len(), MAX_REFERRALS);
+
+    // Pasado el tope el referido puede depositar, pero sin referidor
+    let late = setup.user_with(10 * UNIT);
+    assert_eq!(
+        setup.vault.try_deposit(&late, &(10 * UNIT), &Some(referrer.clone())),
+        Err(Ok(Error::TooManyReferrals))
+    );
+    setup.vault.deposit(&late, &(10 * UNIT), &None);
+    assert_eq!(setup.vault.referrals_of(&referrer).len(), MAX_REFERRALS);
+}
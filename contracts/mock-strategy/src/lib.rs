@@ -0,0 +1,170 @@
+#![no_std]
+extern crate alloc;
+
+use soroban_sdk::{contract, contracterror, contractimpl, symbol_short, token, Address, Env, Symbol};
+
+/// Estrategia de tasa fija para probar el ruteo de fondos de DeFindex sin
+/// depender de un despliegue real de Blend o Soroswap.
+/// El rendimiento solo se puede cosechar si alguien fondeó la estrategia con
+/// tokens por encima del principal.
+const BPS: i128 = 10_000;
+const SECONDS_PER_YEAR: i128 = 365 * 24 * 60 * 60;
+
+#[derive(Clone, Debug)]
+#[soroban_sdk::contracttype]
+pub struct StrategyState {
+    pub admin: Address,
+    pub vault: Address,
+    pub asset: Address,
+    pub apy: i64, // APY en basis points
+    pub principal: i128,
+    pub accrued: i128,
+    pub last_updated: u64,
+}
+
+#[contracterror]
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Error {
+    AlreadyInitialized = 1,
+    NotInitialized = 2,
+    InvalidAmount = 3,
+    InsufficientBalance = 4,
+}
+
+#[contract]
+pub struct FixedRateStrategy;
+
+#[contractimpl]
+impl FixedRateStrategy {
+    /// Configura el vault dueño de los fondos, el asset y la tasa fija.
+    /// Lo firma quien despliega (`admin`), para que nadie más fije el vault.
+    pub fn initialize(
+        env: Env,
+        admin: Address,
+        vault: Address,
+        asset: Address,
+        apy: i64,
+    ) -> Result<(), Error> {
+        admin.require_auth();
+        if env.storage().instance().has(&Self::state_key()) {
+            return Err(Error::AlreadyInitialized);
+        }
+
+        let state = StrategyState {
+            admin,
+            vault,
+            asset,
+            apy,
+            principal: 0,
+            accrued: 0,
+            last_updated: env.ledger().timestamp(),
+        };
+        env.storage().instance().set(&Self::state_key(), &state);
+
+        Ok(())
+    }
+
+    /// Registra `amount` ya transferido por el vault
+    pub fn deposit(env: Env, amount: i128) -> Result<(), Error> {
+        let mut state = Self::load_accrued(&env)?;
+        state.vault.require_auth();
+        if amount <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+
+        state.principal += amount;
+        Self::save(&env, &state);
+
+        Ok(())
+    }
+
+    /// Devuelve `amount` al vault, primero del principal. Del rendimiento solo
+    /// se puede retirar la parte respaldada por tokens.
+    pub fn withdraw(env: Env, amount: i128) -> Result<i128, Error> {
+        let mut state = Self::load_accrued(&env)?;
+        state.vault.require_auth();
+        if amount <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+        if amount > state.principal + Self::backed_accrued(&env, &state) {
+            return Err(Error::InsufficientBalance);
+        }
+
+        if amount <= state.principal {
+            state.principal -= amount;
+        } else {
+            state.accrued -= amount - state.principal;
+            state.principal = 0;
+        }
+        Self::save(&env, &state);
+
+        token::Client::new(&env, &state.asset).transfer(
+            &env.current_contract_address(),
+            &state.vault,
+            &amount,
+        );
+
+        Ok(amount)
+    }
+
+    /// Principal + rendimiento respaldado a nombre del vault
+    pub fn balance(env: Env) -> i128 {
+        match Self::load_accrued(&env) {
+            Ok(state) => state.principal + Self::backed_accrued(&env, &state),
+            Err(_) => 0,
+        }
+    }
+
+    /// Envía al vault el rendimiento acumulado que esté respaldado por tokens
+    pub fn harvest(env: Env) -> Result<i128, Error> {
+        let mut state = Self::load_accrued(&env)?;
+        state.vault.require_auth();
+
+        let amount = Self::backed_accrued(&env, &state);
+        state.accrued -= amount;
+        Self::save(&env, &state);
+
+        if amount > 0 {
+            token::Client::new(&env, &state.asset).transfer(
+                &env.current_contract_address(),
+                &state.vault,
+                &amount,
+            );
+        }
+
+        Ok(amount)
+    }
+
+    /// Estado con el interés simple pendiente aplicado (sin guardar)
+    fn load_accrued(env: &Env) -> Result<StrategyState, Error> {
+        let mut state: StrategyState = env
+            .storage()
+            .instance()
+            .get(&Self::state_key())
+            .ok_or(Error::NotInitialized)?;
+
+        let now = env.ledger().timestamp();
+        let elapsed = now.saturating_sub(state.last_updated) as i128;
+        if elapsed > 0 && state.principal > 0 {
+            state.accrued += state.principal * state.apy as i128 * elapsed / (BPS * SECONDS_PER_YEAR);
+        }
+        state.last_updated = now;
+
+        Ok(state)
+    }
+
+    /// Parte del rendimiento acumulado que los tokens del contrato cubren
+    fn backed_accrued(env: &Env, state: &StrategyState) -> i128 {
+        let held = token::Client::new(env, &state.asset).balance(&env.current_contract_address());
+        state.accrued.min(held - state.principal).max(0)
+    }
+
+    fn save(env: &Env, state: &StrategyState) {
+        env.storage().instance().set(&Self::state_key(), state);
+    }
+
+    fn state_key() -> Symbol {
+        symbol_short!("state")
+    }
+}
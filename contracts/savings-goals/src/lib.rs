@@ -2,18 +2,38 @@
 extern crate alloc;
 
 use soroban_sdk::{
-    contract, contracterror, contractimpl, symbol_short, Address, Bytes, BytesN, Env, IntoVal,
-    Symbol,
+    auth::{ContractContext, InvokerContractAuthEntry, SubContractInvocation},
+    contract, contracterror, contractimpl, symbol_short, token, vec, xdr::ToXdr, Address, Bytes,
+    BytesN, Env, IntoVal, Symbol, Val, Vec,
 };
 
+/// Meta tal como la guardaba la v2 desplegada, bajo la llave `goal`.
+/// Su `saved_amount` era solo un contador: `deposit_to_goal` y
+/// `withdraw_from_goal` de la v2 no movían tokens, así que el contrato nunca
+/// tuvo saldo que respaldara esas metas.
+#[derive(Clone, Debug)]
+#[soroban_sdk::contracttype]
+pub struct GoalV1 {
+    pub target_amount: i128,
+    pub saved_amount: i128,
+    pub deadline_ts: Option<i64>,
+    pub achieved: bool,
+    pub proof_id: Option<BytesN<32>>,
+}
+
+/// Meta actual, bajo la llave `goal_v3`. Los fondos de la "cajita" están en
+/// custodia del contrato; lo invertido son shares de DeFindex a nombre del
+/// contrato (un pool para todas las metas), contabilizadas por meta.
 #[derive(Clone, Debug)]
 #[soroban_sdk::contracttype]
 pub struct Goal {
+    pub id: u32, // Identificador global; cambia al crear una meta nueva
     pub target_amount: i128,
-    pub saved_amount: i128, // Balance guardado en esta "cajita"
+    pub saved_amount: i128, // Tokens guardados en esta "cajita"
     pub deadline_ts: Option<i64>,
     pub achieved: bool,
     pub proof_id: Option<BytesN<32>>,
+    pub invested_shares: i128, // Shares de DeFindex que pertenecen a esta meta
 }
 
 #[contracterror]
@@ -26,6 +46,25 @@ pub enum Error {
     InvalidAmount = 4,
     NotAuthorized = 5,
     VerifierCallFailed = 6,
+    Paused = 7,
+    DeFindexNotConfigured = 8,
+    GoalNotAchieved = 9,
+    AssetNotConfigured = 10,
+    DeFindexCallFailed = 11,
+    InsufficientFunds = 12,
+    ProofRejected = 13,
+    PoolCapExceeded = 14,
+}
+
+/// Circuito de los proofs de metas en el registro de VKs del verificador
+const SAVINGS_CIRCUIT: &str = "savings_proof";
+
+/// Resultado de `consume_proof` en el verificador: proof_id y public inputs
+#[derive(Clone, Debug)]
+#[soroban_sdk::contracttype]
+pub struct VerificationResult {
+    pub proof_id: BytesN<32>,
+    pub public_inputs: Vec<BytesN<32>>,
 }
 
 #[contract]
@@ -39,7 +78,116 @@ impl SavingsGoals {
         env.storage().instance().set(&Self::verifier_key(), &verifier);
     }
 
-    /// Crea o actualiza la meta de ahorro del invocador.
+    /// Configura el token que custodian las "cajitas" (el mismo asset de DeFindex).
+    pub fn set_asset(env: Env, caller: Address, asset: Address) {
+        Self::assert_admin(&env, &caller);
+        env.storage().instance().set(&Self::asset_key(), &asset);
+    }
+
+    /// Configura el contrato DeFindex al que se mueven los saldos de las metas.
+    /// Las metas invierten con la dirección de este contrato, así que debe poder
+    /// depositar en DeFindex (registro de estudiantes y límites por usuario).
+    pub fn set_defindex(env: Env, caller: Address, defindex: Address) {
+        Self::assert_admin(&env, &caller);
+        env.storage().instance().set(&Self::defindex_key(), &defindex);
+    }
+
+    /// Configura el tope, en assets, del pool que todas las metas comparten en
+    /// DeFindex. Todo lo invertido es una sola posición de este contrato: gana
+    /// el APY de su nivel y cuenta contra el límite por usuario de DeFindex, así
+    /// que el tope no debería superar ese límite. Con tope 0 (por defecto) no se
+    /// invierte nada.
+    pub fn set_pool_cap(env: Env, caller: Address, cap: i128) -> Result<(), Error> {
+        Self::assert_admin(&env, &caller);
+        if cap < 0 {
+            return Err(Error::InvalidAmount);
+        }
+        env.storage().instance().set(&Self::pool_cap_key(), &cap);
+        Ok(())
+    }
+
+    /// Tope del pool en DeFindex (en assets).
+    pub fn get_pool_cap(env: Env) -> i128 {
+        env.storage()
+            .instance()
+            .get(&Self::pool_cap_key())
+            .unwrap_or(0)
+    }
+
+    /// Shares de DeFindex que este contrato tiene a nombre de todas las metas.
+    pub fn pooled_shares(env: Env) -> i128 {
+        env.storage()
+            .instance()
+            .get(&Self::pooled_shares_key())
+            .unwrap_or(0)
+    }
+
+    /// Dueño de la meta `goal_id`, si existe.
+    pub fn get_goal_owner(env: Env, goal_id: u32) -> Option<Address> {
+        env.storage()
+            .persistent()
+            .get(&Self::goal_owner_key(goal_id))
+    }
+
+    /// Configura el guardián que puede pausar el contrato.
+    pub fn set_guardian(env: Env, caller: Address, guardian: Address) {
+        Self::assert_admin(&env, &caller);
+        env.storage().instance().set(&Self::guardian_key(), &guardian);
+    }
+
+    /// Pausa metas, depósitos, retiros normales y envío de proofs (guardián o admin).
+    /// Los usuarios pueden seguir usando `emergency_withdraw`.
+    pub fn pause(env: Env, caller: Address) {
+        Self::assert_guardian(&env, &caller);
+        env.storage().instance().set(&Self::paused_key(), &true);
+        env.events().publish((symbol_short!("paused"), caller), ());
+    }
+
+    /// Reanuda la operación normal (guardián o admin).
+    pub fn unpause(env: Env, caller: Address) {
+        Self::assert_guardian(&env, &caller);
+        env.storage().instance().set(&Self::paused_key(), &false);
+        env.events().publish((symbol_short!("unpaused"), caller), ());
+    }
+
+    /// Indica si el contrato está en pausa.
+    pub fn is_paused(env: Env) -> bool {
+        env.storage()
+            .instance()
+            .get(&Self::paused_key())
+            .unwrap_or(false)
+    }
+
+    /// Retira todo el saldo de la "cajita" del usuario. Disponible aun en pausa.
+    /// También intenta rescatar de DeFindex las shares de la meta; si DeFindex
+    /// no responde (por ejemplo, en pausa), las shares siguen asignadas a la meta
+    /// y se pueden rescatar después.
+    pub fn emergency_withdraw(env: Env, user: Address) -> Result<i128, Error> {
+        user.require_auth();
+
+        let mut goal = Self::load_goal(&env, &user).ok_or(Error::GoalNotFound)?;
+
+        if goal.invested_shares > 0 && Self::redeem(&env, &mut goal).is_err() {
+            env.events()
+                .publish((symbol_short!("rdm_err"), user.clone()), goal.invested_shares);
+        }
+
+        let amount = goal.saved_amount;
+        goal.saved_amount = 0;
+
+        Self::save_goal(&env, &user, &mut goal);
+        if amount > 0 {
+            Self::token(&env)?.transfer(&env.current_contract_address(), &user, &amount);
+        }
+
+        env.events()
+            .publish((symbol_short!("emrg_wd"), user), amount);
+
+        Ok(amount)
+    }
+
+    /// Crea o actualiza la meta de ahorro del invocador. Si la meta anterior ya
+    /// se cumplió, se crea una meta nueva con otro `id` (conservando el saldo).
     pub fn set_savings_goal(
         env: Env,
         user: Address,
@@ -47,95 +195,172 @@ impl SavingsGoals {
         deadline_ts: Option<i64>,
     ) {
         user.require_auth();
+        if Self::is_paused(env.clone()) {
+            env.panic_with_error(Error::Paused);
+        }
         if target_amount <= 0 {
             env.panic_with_error(Error::InvalidAmount);
         }
 
-        // Si ya existe una meta, preservar el saved_amount
-        let saved_amount: i128 = match env.storage().persistent().get::<_, Goal>(&Self::goal_key(&user)) {
-            Some(existing) => existing.saved_amount,
-            None => 0,
+        // Si ya existe una meta, preservar el saved_amount y lo invertido;
+        // una meta sin cumplir conserva su id
+        let (id, saved_amount, invested_shares) = match Self::load_goal(&env, &user) {
+            Some(existing) => (
+                if existing.achieved { 0 } else { existing.id },
+                existing.saved_amount,
+                existing.invested_shares,
+            ),
+            None => (0, 0, 0),
         };
 
-        let goal = Goal {
+        let mut goal = Goal {
+            id,
             target_amount,
             saved_amount,
             deadline_ts,
             achieved: false,
             proof_id: None,
+            invested_shares,
         };
 
-        env.storage()
-            .persistent()
-            .set(&Self::goal_key(&user), &goal);
+        Self::save_goal(&env, &user, &mut goal);
     }
 
     /// Deposita dinero en la "cajita" de ahorro del usuario.
-    /// El dinero se suma al saved_amount de la meta.
+    /// Los tokens pasan a custodia del contrato y se suman al saved_amount.
     pub fn deposit_to_goal(
         env: Env,
         user: Address,
         amount: i128,
     ) -> Result<i128, Error> {
         user.require_auth();
+        Self::assert_not_paused(&env)?;
         
         if amount <= 0 {
             return Err(Error::InvalidAmount);
         }
 
-        let mut goal: Goal = env
-            .storage()
-            .persistent()
-            .get(&Self::goal_key(&user))
-            .ok_or(Error::GoalNotFound)?;
+        let mut goal = Self::load_goal(&env, &user).ok_or(Error::GoalNotFound)?;
 
         if goal.achieved {
             return Err(Error::AlreadyAchieved);
         }
 
+        Self::token(&env)?.transfer(&user, &env.current_contract_address(), &amount);
         goal.saved_amount += amount;
 
-        env.storage()
-            .persistent()
-            .set(&Self::goal_key(&user), &goal);
+        // Auto-sweep: mandar a DeFindex lo que exceda el umbral configurado.
+        // Es de mejor esfuerzo: si DeFindex rechaza el depósito, el saldo se
+        // queda en la cajita y el depósito del usuario sigue adelante.
+        if let Some(threshold) = Self::get_auto_sweep(env.clone(), user.clone()) {
+            let excess = goal.saved_amount - threshold;
+            if excess > 0 && Self::invest(&env, &mut goal, excess).is_err() {
+                env.events()
+                    .publish((symbol_short!("sweep_err"), user.clone()), excess);
+            }
+        }
+
+        Self::save_goal(&env, &user, &mut goal);
 
         Ok(goal.saved_amount)
     }
 
+    /// Configura el umbral de auto-sweep del usuario. Mientras la meta esté activa,
+    /// el saldo que exceda `threshold` se deposita en DeFindex. `None` lo desactiva.
+    pub fn set_auto_sweep(env: Env, user: Address, threshold: Option<i128>) -> Result<(), Error> {
+        user.require_auth();
+
+        match threshold {
+            Some(threshold) => {
+                if threshold < 0 {
+                    return Err(Error::InvalidAmount);
+                }
+                if !env.storage().instance().has(&Self::defindex_key()) {
+                    return Err(Error::DeFindexNotConfigured);
+                }
+                env.storage()
+                    .persistent()
+                    .set(&Self::sweep_key(&user), &threshold);
+            }
+            None => env.storage().persistent().remove(&Self::sweep_key(&user)),
+        }
+
+        Ok(())
+    }
+
+    /// Obtiene el umbral de auto-sweep del usuario, si está activo.
+    pub fn get_auto_sweep(env: Env, user: Address) -> Option<i128> {
+        env.storage().persistent().get(&Self::sweep_key(&user))
+    }
+
+    /// Invierte en DeFindex todo el saldo guardado de la meta cumplida `goal_id`.
+    /// Requiere la autorización del dueño. Las shares quedan en el pool de este
+    /// contrato (ver `set_pool_cap`) y se asignan a la meta; se recuperan con
+    /// `withdraw_from_goal`. Retorna el monto depositado.
+    pub fn sweep_to_defindex(env: Env, goal_id: u32) -> Result<i128, Error> {
+        let user = Self::get_goal_owner(env.clone(), goal_id).ok_or(Error::GoalNotFound)?;
+        user.require_auth();
+        Self::assert_not_paused(&env)?;
+
+        let mut goal = Self::load_goal(&env, &user).ok_or(Error::GoalNotFound)?;
+        if goal.id != goal_id {
+            return Err(Error::GoalNotFound);
+        }
+
+        if !goal.achieved {
+            return Err(Error::GoalNotAchieved);
+        }
+
+        let amount = goal.saved_amount;
+        if amount <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+
+        Self::invest(&env, &mut goal, amount)?;
+        Self::save_goal(&env, &user, &mut goal);
+
+        env.events()
+            .publish((symbol_short!("swept"), user, goal_id), amount);
+
+        Ok(amount)
+    }
+
     /// Retira dinero de la "cajita" de ahorro (opcional, para flexibilidad).
+    /// Si el saldo guardado no alcanza, se rescatan de DeFindex todas las shares
+    /// de la meta; lo que sobre queda guardado en la cajita.
     pub fn withdraw_from_goal(
         env: Env,
         user: Address,
         amount: i128,
     ) -> Result<i128, Error> {
         user.require_auth();
+        Self::assert_not_paused(&env)?;
         
         if amount <= 0 {
             return Err(Error::InvalidAmount);
         }
 
-        let mut goal: Goal = env
-            .storage()
-            .persistent()
-            .get(&Self::goal_key(&user))
-            .ok_or(Error::GoalNotFound)?;
+        let mut goal = Self::load_goal(&env, &user).ok_or(Error::GoalNotFound)?;
 
+        if goal.saved_amount < amount && goal.invested_shares > 0 {
+            Self::redeem(&env, &mut goal)?;
+        }
         if goal.saved_amount < amount {
-            return Err(Error::InvalidAmount); // Fondos insuficientes
+            return Err(Error::InsufficientFunds);
         }
 
         goal.saved_amount -= amount;
-
-        env.storage()
-            .persistent()
-            .set(&Self::goal_key(&user), &goal);
+        Self::save_goal(&env, &user, &mut goal);
+        Self::token(&env)?.transfer(&env.current_contract_address(), &user, &amount);
 
         Ok(goal.saved_amount)
     }
 
-    /// Obtiene la meta de ahorro de un usuario.
+    /// Obtiene la meta de ahorro de un usuario. Una meta de la v2 se muestra ya
+    /// convertida, con el saldo en cero (la v2 no custodiaba tokens) y con
+    /// `id` 0 hasta que se guarde por primera vez.
     pub fn get_savings_goal(env: Env, user: Address) -> Option<Goal> {
-        env.storage().persistent().get(&Self::goal_key(&user))
+        Self::load_goal(&env, &user)
     }
 
     /// Envía un proof blob (generado con Noir + Ultrahonk) y marca la meta como cumplida.
@@ -146,11 +371,8 @@ impl SavingsGoals {
         proof_blob: Bytes,
     ) -> Result<BytesN<32>, Error> {
         user.require_auth();
-        let mut goal: Goal = env
-            .storage()
-            .persistent()
-            .get(&Self::goal_key(&user))
-            .ok_or(Error::GoalNotFound)?;
+        Self::assert_not_paused(&env)?;
+        let mut goal = Self::load_goal(&env, &user).ok_or(Error::GoalNotFound)?;
 
         if goal.achieved {
             return Err(Error::AlreadyAchieved);
@@ -162,31 +384,240 @@ impl SavingsGoals {
             .get(&Self::verifier_key())
             .ok_or(Error::VerifierNotConfigured)?;
 
-        let proof_id = Self::invoke_verifier(&env, &verifier, proof_blob)?;
+        // El único public input del circuito es su output (saved - target)
+        let expected = vec![&env, BytesN::from_array(&env, &[0u8; 32])];
+        let proof_id =
+            Self::consume_proof(&env, &verifier, &user, proof_blob, expected, vec![&env, 0])?
+                .proof_id;
 
         goal.achieved = true;
         goal.proof_id = Some(proof_id.clone());
 
-        env.storage()
-            .persistent()
-            .set(&Self::goal_key(&user), &goal);
+        Self::save_goal(&env, &user, &mut goal);
 
         Ok(proof_id)
     }
 
-    fn invoke_verifier(
+    /// Verifica y consume el proof en el verificador con la meta del usuario
+    /// (una por usuario) como contexto. Un proof inválido o ya usado se reporta
+    /// como error en lugar de abortar. El verificador compara los public inputs
+    /// con `expected` salvo en las posiciones de `wildcards`.
+    fn consume_proof(
         env: &Env,
         verifier: &Address,
+        user: &Address,
         proof_blob: Bytes,
-    ) -> Result<BytesN<32>, Error> {
-        use soroban_sdk::Vec;
-
-        let mut args = Vec::new(env);
+        expected: Vec<BytesN<32>>,
+        wildcards: Vec<u32>,
+    ) -> Result<VerificationResult, Error> {
+        let context: BytesN<32> = env.crypto().sha256(&user.clone().to_xdr(env)).into();
+
+        let mut args: Vec<Val> = Vec::new(env);
+        args.push_back(env.current_contract_address().into_val(env));
+        args.push_back(Some(Symbol::new(env, SAVINGS_CIRCUIT)).into_val(env));
         args.push_back(proof_blob.into_val(env));
-        let fn_symbol = Symbol::new(env, "verify_proof_with_stored_vk");
-        let result: BytesN<32> = env.invoke_contract(verifier, &fn_symbol, args);
+        args.push_back(context.into_val(env));
+        args.push_back(expected.into_val(env));
+        args.push_back(wildcards.into_val(env));
+
+        match env.try_invoke_contract::<VerificationResult, soroban_sdk::Error>(
+            verifier,
+            &Symbol::new(env, "consume_proof"),
+            args,
+        ) {
+            Ok(Ok(result)) => Ok(result),
+            Err(Ok(_)) => Err(Error::ProofRejected),
+            _ => Err(Error::VerifierCallFailed),
+        }
+    }
+
+    /// Deposita `amount` del saldo guardado en DeFindex con la dirección de este
+    /// contrato y asigna las shares acuñadas a la meta. Falla con
+    /// PoolCapExceeded si el pool pasaría su tope. Si DeFindex falla no se
+    /// toca la meta.
+    fn invest(env: &Env, goal: &mut Goal, amount: i128) -> Result<(), Error> {
+        let defindex = Self::get_defindex(env)?;
+        let asset = Self::get_asset(env)?;
+        let this = env.current_contract_address();
+
+        let pooled_shares = Self::pooled_shares(env.clone());
+        let pooled = if pooled_shares > 0 {
+            let args: Vec<Val> = (pooled_shares,).into_val(env);
+            Self::call_defindex(env, &defindex, "convert_to_assets", args)?
+        } else {
+            0
+        };
+        if pooled + amount > Self::get_pool_cap(env.clone()) {
+            return Err(Error::PoolCapExceeded);
+        }
+
+        // DeFindex cobra con `transfer(this, defindex, amount)`: autorizarla aquí
+        env.authorize_as_current_contract(vec![
+            env,
+            InvokerContractAuthEntry::Contract(SubContractInvocation {
+                context: ContractContext {
+                    contract: asset,
+                    fn_name: Symbol::new(env, "transfer"),
+                    args: (this.clone(), defindex.clone(), amount).into_val(env),
+                },
+                sub_invocations: Vec::new(env),
+            }),
+        ]);
+
+        let args: Vec<Val> = (this, amount, Option::<Address>::None).into_val(env);
+        let shares: i128 = Self::call_defindex(env, &defindex, "deposit", args)?;
+
+        goal.saved_amount -= amount;
+        goal.invested_shares += shares;
+        Self::set_pooled_shares(env, pooled_shares + shares);
+
+        Ok(())
+    }
+
+    /// Rescata de DeFindex todas las shares de la meta hacia su saldo guardado.
+    fn redeem(env: &Env, goal: &mut Goal) -> Result<(), Error> {
+        let defindex = Self::get_defindex(env)?;
+
+        let args: Vec<Val> =
+            (env.current_contract_address(), goal.invested_shares).into_val(env);
+        let assets: i128 = Self::call_defindex(env, &defindex, "redeem", args)?;
+
+        Self::set_pooled_shares(env, Self::pooled_shares(env.clone()) - goal.invested_shares);
+        goal.invested_shares = 0;
+        goal.saved_amount += assets;
+
+        Ok(())
+    }
+
+    /// Llama a DeFindex sin abortar: cualquier falla se reporta como error
+    fn call_defindex(
+        env: &Env,
+        defindex: &Address,
+        function: &str,
+        args: Vec<Val>,
+    ) -> Result<i128, Error> {
+        match env.try_invoke_contract::<i128, soroban_sdk::Error>(
+            defindex,
+            &Symbol::new(env, function),
+            args,
+        ) {
+            Ok(Ok(value)) => Ok(value),
+            _ => Err(Error::DeFindexCallFailed),
+        }
+    }
+
+    /// Lee la meta del usuario. Una meta de la v2 se convierte al formato actual
+    /// con saldo cero: su saved_amount no tenía tokens en custodia.
+    fn load_goal(env: &Env, user: &Address) -> Option<Goal> {
+        if let Some(goal) = env.storage().persistent().get(&Self::goal_key(user)) {
+            return Some(goal);
+        }
+        let legacy: GoalV1 = env.storage().persistent().get(&Self::legacy_goal_key(user))?;
+        Some(Goal {
+            id: 0,
+            target_amount: legacy.target_amount,
+            saved_amount: 0,
+            deadline_ts: legacy.deadline_ts,
+            achieved: legacy.achieved,
+            proof_id: legacy.proof_id,
+            invested_shares: 0,
+        })
+    }
+
+    /// Guarda la meta en el formato actual y le asigna un id si no tiene; si
+    /// venía de la v2, borra la entrada vieja y emite el contador que tenía.
+    fn save_goal(env: &Env, user: &Address, goal: &mut Goal) {
+        if goal.id == 0 {
+            let id: u32 = env
+                .storage()
+                .instance()
+                .get(&Self::goal_seq_key())
+                .unwrap_or(0)
+                + 1;
+            env.storage().instance().set(&Self::goal_seq_key(), &id);
+            env.storage().persistent().set(&Self::goal_owner_key(id), user);
+            goal.id = id;
+        }
+        let legacy_key = Self::legacy_goal_key(user);
+        if let Some(legacy) = env.storage().persistent().get::<_, GoalV1>(&legacy_key) {
+            env.storage().persistent().remove(&legacy_key);
+            env.events()
+                .publish((symbol_short!("goal_mig"), user.clone()), legacy.saved_amount);
+        }
+        env.storage().persistent().set(&Self::goal_key(user), goal);
+    }
+
+    fn get_asset(env: &Env) -> Result<Address, Error> {
+        env.storage()
+            .instance()
+            .get(&Self::asset_key())
+            .ok_or(Error::AssetNotConfigured)
+    }
+
+    fn token(env: &Env) -> Result<token::Client<'_>, Error> {
+        Ok(token::Client::new(env, &Self::get_asset(env)?))
+    }
+
+    fn get_defindex(env: &Env) -> Result<Address, Error> {
+        env.storage()
+            .instance()
+            .get(&Self::defindex_key())
+            .ok_or(Error::DeFindexNotConfigured)
+    }
+
+    fn assert_not_paused(env: &Env) -> Result<(), Error> {
+        if Self::is_paused(env.clone()) {
+            return Err(Error::Paused);
+        }
+        Ok(())
+    }
+
+    /// El guardián o el admin pueden pausar y reanudar.
+    fn assert_guardian(env: &Env, caller: &Address) {
+        let guardian: Option<Address> = env.storage().instance().get(&Self::guardian_key());
+        if guardian.as_ref() == Some(caller) {
+            caller.require_auth();
+            return;
+        }
+        Self::assert_admin(env, caller);
+    }
+
+    fn set_pooled_shares(env: &Env, shares: i128) {
+        env.storage()
+            .instance()
+            .set(&Self::pooled_shares_key(), &shares);
+    }
+
+    fn pool_cap_key() -> Symbol {
+        symbol_short!("pool_cap")
+    }
+
+    fn pooled_shares_key() -> Symbol {
+        symbol_short!("pooled")
+    }
 
-        Ok(result)
+    fn goal_seq_key() -> Symbol {
+        symbol_short!("goal_seq")
+    }
+
+    fn goal_owner_key(goal_id: u32) -> (Symbol, u32) {
+        (symbol_short!("goal_own"), goal_id)
+    }
+
+    fn defindex_key() -> Symbol {
+        symbol_short!("defindex")
+    }
+
+    fn sweep_key(user: &Address) -> (Symbol, Address) {
+        (symbol_short!("sweep"), user.clone())
+    }
+
+    fn guardian_key() -> Symbol {
+        symbol_short!("guardian")
+    }
+
+    fn paused_key() -> Symbol {
+        symbol_short!("paused")
     }
 
     fn verifier_key() -> Symbol {
@@ -194,9 +625,17 @@ impl SavingsGoals {
     }
 
     fn goal_key(user: &Address) -> (Symbol, Address) {
+        (symbol_short!("goal_v3"), user.clone())
+    }
+
+    fn legacy_goal_key(user: &Address) -> (Symbol, Address) {
         (symbol_short!("goal"), user.clone())
     }
 
+    fn asset_key() -> Symbol {
+        symbol_short!("asset")
+    }
+
     fn admin_key() -> Symbol {
         symbol_short!("admin")
     }
@@ -220,3 +659,5 @@ impl SavingsGoals {
     }
 }
 
+
+mod test;
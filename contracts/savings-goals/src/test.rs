@@ -0,0 +1,433 @@
+#![cfg(test)]
+extern crate std;
+
+use super::*;
+use soroban_sdk::{
+    contract, contracterror, contractimpl,
+    testutils::Address as _,
+    token::{StellarAssetClient, TokenClient},
+};
+
+#[contracterror]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MockDeFindexError {
+    Rejected = 1,
+}
+
+/// DeFindex mínimo: shares 1:1 con los assets, y puede rechazar depósitos
+#[contract]
+pub struct MockDeFindex;
+
+#[contractimpl]
+impl MockDeFindex {
+    pub fn init(env: Env, asset: Address) {
+        env.storage()
+            .instance()
+            .set(&symbol_short!("asset"), &asset);
+    }
+
+    pub fn set_failing(env: Env, failing: bool) {
+        env.storage()
+            .instance()
+            .set(&symbol_short!("failing"), &failing);
+    }
+
+    pub fn deposit(
+        env: Env,
+        user: Address,
+        amount: i128,
+        _referrer: Option<Address>,
+    ) -> Result<i128, MockDeFindexError> {
+        user.require_auth();
+        if env
+            .storage()
+            .instance()
+            .get(&symbol_short!("failing"))
+            .unwrap_or(false)
+        {
+            return Err(MockDeFindexError::Rejected);
+        }
+        Self::token(&env).transfer(&user, &env.current_contract_address(), &amount);
+        let shares = Self::shares(env.clone(), user.clone()) + amount;
+        env.storage().persistent().set(&user, &shares);
+        Ok(amount)
+    }
+
+    pub fn redeem(env: Env, user: Address, shares: i128) -> Result<i128, MockDeFindexError> {
+        user.require_auth();
+        if env
+            .storage()
+            .instance()
+            .get(&symbol_short!("failing"))
+            .unwrap_or(false)
+        {
+            return Err(MockDeFindexError::Rejected);
+        }
+        let held = Self::shares(env.clone(), user.clone());
+        if shares > held {
+            return Err(MockDeFindexError::Rejected);
+        }
+        env.storage().persistent().set(&user, &(held - shares));
+        Self::token(&env).transfer(&env.current_contract_address(), &user, &shares);
+        Ok(shares)
+    }
+
+    pub fn shares(env: Env, user: Address) -> i128 {
+        env.storage().persistent().get(&user).unwrap_or(0)
+    }
+
+    pub fn convert_to_assets(_env: Env, shares: i128) -> i128 {
+        shares
+    }
+
+    fn token(env: &Env) -> TokenClient<'_> {
+        let asset: Address = env
+            .storage()
+            .instance()
+            .get(&symbol_short!("asset"))
+            .unwrap();
+        TokenClient::new(env, &asset)
+    }
+}
+
+#[contracterror]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MockVerifierError {
+    ProofAlreadyUsed = 5,
+    PublicInputMismatch = 9,
+}
+
+/// Verificador mínimo: acepta una sola vez cualquier proof cuyos public inputs
+/// coincidan con `expected` y guarda el contexto
+#[contract]
+pub struct MockVerifier;
+
+#[contractimpl]
+impl MockVerifier {
+    pub fn consume_proof(
+        env: Env,
+        caller: Address,
+        _circuit_id: Option<Symbol>,
+        proof_blob: Bytes,
+        context: BytesN<32>,
+        expected: Vec<BytesN<32>>,
+        wildcards: Vec<u32>,
+    ) -> Result<VerificationResult, MockVerifierError> {
+        caller.require_auth();
+        let proof_id: BytesN<32> = env.crypto().keccak256(&proof_blob).into();
+        if env.storage().persistent().has(&proof_id) {
+            return Err(MockVerifierError::ProofAlreadyUsed);
+        }
+
+        // Blob [count][public inputs][proof], comparado como en los verificadores
+        let count = if proof_blob.len() >= 4 {
+            let mut header = [0u8; 4];
+            proof_blob.slice(0..4).copy_into_slice(&mut header);
+            u32::from_be_bytes(header)
+        } else {
+            0
+        };
+        if count != expected.len() || proof_blob.len() < 4 + count * 32 {
+            return Err(MockVerifierError::PublicInputMismatch);
+        }
+        let mut public_inputs = Vec::new(&env);
+        for i in 0..count {
+            let mut field = [0u8; 32];
+            proof_blob
+                .slice(4 + i * 32..4 + (i + 1) * 32)
+                .copy_into_slice(&mut field);
+            let input = BytesN::from_array(&env, &field);
+            if !wildcards.contains(i) && input != expected.get_unchecked(i) {
+                return Err(MockVerifierError::PublicInputMismatch);
+            }
+            public_inputs.push_back(input);
+        }
+
+        env.storage().persistent().set(&proof_id, &context);
+        Ok(VerificationResult {
+            proof_id,
+            public_inputs,
+        })
+    }
+
+    pub fn context(env: Env, proof_id: BytesN<32>) -> Option<BytesN<32>> {
+        env.storage().persistent().get(&proof_id)
+    }
+}
+
+struct Setup<'a> {
+    env: Env,
+    goals: SavingsGoalsClient<'a>,
+    defindex: MockDeFindexClient<'a>,
+    token: TokenClient<'a>,
+    admin: Address,
+    user: Address,
+}
+
+impl Setup<'_> {
+    fn new() -> Self {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let admin = Address::generate(&env);
+        let asset = env
+            .register_stellar_asset_contract_v2(admin.clone())
+            .address();
+
+        let defindex = MockDeFindexClient::new(&env, &env.register(MockDeFindex, ()));
+        defindex.init(&asset);
+
+        let goals = SavingsGoalsClient::new(&env, &env.register(SavingsGoals, ()));
+        goals.set_asset(&admin, &asset);
+        goals.set_defindex(&admin, &defindex.address);
+        goals.set_pool_cap(&admin, &10_000);
+
+        let user = Address::generate(&env);
+        StellarAssetClient::new(&env, &asset).mint(&user, &1_000);
+        goals.set_savings_goal(&user, &500, &None);
+
+        Setup {
+            token: TokenClient::new(&env, &asset),
+            env,
+            goals,
+            defindex,
+            admin,
+            user,
+        }
+    }
+
+    fn goal(&self) -> Goal {
+        self.goals.get_savings_goal(&self.user).unwrap()
+    }
+}
+
+#[test]
+fn goal_balance_is_backed_by_custodied_tokens() {
+    let setup = Setup::new();
+
+    assert_eq!(setup.goals.deposit_to_goal(&setup.user, &300), 300);
+    assert_eq!(setup.token.balance(&setup.goals.address), 300);
+    assert_eq!(setup.token.balance(&setup.user), 700);
+
+    assert_eq!(setup.goals.withdraw_from_goal(&setup.user, &100), 200);
+    assert_eq!(setup.token.balance(&setup.goals.address), 200);
+    assert_eq!(setup.token.balance(&setup.user), 800);
+
+    assert_eq!(
+        setup.goals.try_withdraw_from_goal(&setup.user, &201),
+        Err(Ok(Error::InsufficientFunds))
+    );
+}
+
+#[test]
+fn auto_sweep_invests_under_contract_and_withdraw_redeems() {
+    let setup = Setup::new();
+    setup.goals.set_auto_sweep(&setup.user, &Some(50));
+
+    assert_eq!(setup.goals.deposit_to_goal(&setup.user, &200), 50);
+    let goal = setup.goal();
+    assert_eq!(goal.invested_shares, 150);
+
+    // Las shares son del contrato, no del usuario: no puede sacarlas por fuera
+    assert_eq!(setup.defindex.shares(&setup.goals.address), 150);
+    assert_eq!(setup.defindex.shares(&setup.user), 0);
+
+    // El retiro rescata todas las shares de la meta y deja el resto guardado
+    assert_eq!(setup.goals.withdraw_from_goal(&setup.user, &120), 80);
+    let goal = setup.goal();
+    assert_eq!(goal.invested_shares, 0);
+    assert_eq!(setup.token.balance(&setup.user), 920);
+    assert_eq!(setup.token.balance(&setup.goals.address), 80);
+}
+
+#[test]
+fn failed_auto_sweep_keeps_the_deposit() {
+    let setup = Setup::new();
+    setup.goals.set_auto_sweep(&setup.user, &Some(50));
+    setup.defindex.set_failing(&true);
+
+    assert_eq!(setup.goals.deposit_to_goal(&setup.user, &200), 200);
+    assert_eq!(setup.goal().invested_shares, 0);
+    assert_eq!(setup.token.balance(&setup.goals.address), 200);
+}
+
+#[test]
+fn legacy_goal_is_read_and_migrated() {
+    let setup = Setup::new();
+    let user = Address::generate(&setup.env);
+    StellarAssetClient::new(&setup.env, &setup.token.address).mint(&user, &100);
+
+    // Meta escrita por la v2: solo un contador, sin tokens detrás
+    setup.env.as_contract(&setup.goals.address, || {
+        let legacy = GoalV1 {
+            target_amount: 400,
+            saved_amount: 250,
+            deadline_ts: Some(1_700_000_000),
+            achieved: false,
+            proof_id: None,
+        };
+        setup
+            .env
+            .storage()
+            .persistent()
+            .set(&SavingsGoals::legacy_goal_key(&user), &legacy);
+    });
+
+    // El contador de la v2 no tiene tokens en custodia: se lee con saldo cero
+    assert_eq!(setup.token.balance(&setup.goals.address), 0);
+    let goal = setup.goals.get_savings_goal(&user).unwrap();
+    assert_eq!(goal.target_amount, 400);
+    assert_eq!(goal.saved_amount, 0);
+    assert_eq!(goal.deadline_ts, Some(1_700_000_000));
+    assert_eq!(goal.id, 0);
+
+    assert_eq!(setup.goals.deposit_to_goal(&user, &100), 100);
+    let goal = setup.goals.get_savings_goal(&user).unwrap();
+    assert_eq!(setup.goals.get_goal_owner(&goal.id), Some(user.clone()));
+    setup.env.as_contract(&setup.goals.address, || {
+        let storage = setup.env.storage().persistent();
+        assert!(!storage.has(&SavingsGoals::legacy_goal_key(&user)));
+        assert!(storage.has(&SavingsGoals::goal_key(&user)));
+    });
+}
+
+#[test]
+fn emergency_withdraw_redeems_invested_shares() {
+    let setup = Setup::new();
+    setup.goals.set_auto_sweep(&setup.user, &Some(50));
+    setup.goals.deposit_to_goal(&setup.user, &200);
+    setup.goals.pause(&setup.admin);
+
+    assert_eq!(setup.goals.emergency_withdraw(&setup.user), 200);
+    let goal = setup.goal();
+    assert_eq!((goal.saved_amount, goal.invested_shares), (0, 0));
+    assert_eq!(setup.token.balance(&setup.user), 1_000);
+}
+
+#[test]
+fn emergency_withdraw_keeps_shares_when_defindex_fails() {
+    let setup = Setup::new();
+    setup.goals.set_auto_sweep(&setup.user, &Some(50));
+    setup.goals.deposit_to_goal(&setup.user, &200);
+    setup.goals.pause(&setup.admin);
+    setup.defindex.set_failing(&true);
+
+    // Se paga lo guardado; las shares siguen asignadas a la meta
+    assert_eq!(setup.goals.emergency_withdraw(&setup.user), 50);
+    assert_eq!(setup.goal().invested_shares, 150);
+
+    setup.defindex.set_failing(&false);
+    setup.goals.unpause(&setup.admin);
+    assert_eq!(setup.goals.withdraw_from_goal(&setup.user, &150), 0);
+    assert_eq!(setup.token.balance(&setup.user), 1_000);
+}
+
+/// Proof blob [count][public inputs][proof] del circuito de metas: su único
+/// public input es el output `saved - target`
+fn goal_blob(env: &Env, surplus: u64) -> Bytes {
+    let mut blob = Bytes::from_array(env, &1u32.to_be_bytes());
+    blob.extend_from_array(&[0u8; 24]);
+    blob.extend_from_array(&surplus.to_be_bytes());
+    blob.extend_from_array(&[7u8; 100]);
+    blob
+}
+
+#[test]
+fn proof_is_consumed_once_with_the_goal_as_context() {
+    let s = Setup::new();
+    let verifier = MockVerifierClient::new(&s.env, &s.env.register(MockVerifier, ()));
+    s.goals.set_verifier(&s.admin, &verifier.address);
+    let blob = goal_blob(&s.env, 0);
+
+    let proof_id = s.goals.submit_proof(&s.user, &blob);
+    assert!(s.goal().achieved);
+    assert_eq!(s.goal().proof_id, Some(proof_id.clone()));
+    let goal_id: BytesN<32> = s.env.crypto().sha256(&s.user.clone().to_xdr(&s.env)).into();
+    assert_eq!(verifier.context(&proof_id), Some(goal_id));
+
+    // El mismo blob ya se consumió: otra meta no lo puede reutilizar
+    let other = Address::generate(&s.env);
+    s.goals.set_savings_goal(&other, &500, &None);
+    assert_eq!(
+        s.goals.try_submit_proof(&other, &blob),
+        Err(Ok(Error::ProofRejected))
+    );
+    assert!(!s.goals.get_savings_goal(&other).unwrap().achieved);
+
+    // Otro layout de public inputs no pasa el verificador
+    assert_eq!(
+        s.goals
+            .try_submit_proof(&other, &Bytes::from_array(&s.env, &[7u8; 8])),
+        Err(Ok(Error::ProofRejected))
+    );
+}
+
+#[test]
+fn sweep_moves_achieved_goal_into_the_pool() {
+    let s = Setup::new();
+    let verifier = MockVerifierClient::new(&s.env, &s.env.register(MockVerifier, ()));
+    s.goals.set_verifier(&s.admin, &verifier.address);
+    s.goals.deposit_to_goal(&s.user, &300);
+    let goal_id = s.goal().id;
+
+    assert_eq!(
+        s.goals.try_sweep_to_defindex(&goal_id),
+        Err(Ok(Error::GoalNotAchieved))
+    );
+    s.goals.submit_proof(&s.user, &goal_blob(&s.env, 0));
+
+    assert_eq!(s.goals.sweep_to_defindex(&goal_id), 300);
+    let goal = s.goal();
+    assert_eq!((goal.saved_amount, goal.invested_shares), (0, 300));
+    assert_eq!(s.goals.pooled_shares(), 300);
+    assert_eq!(s.defindex.shares(&s.goals.address), 300);
+
+    // Una meta nueva recibe otro id; el id anterior ya no se puede barrer
+    s.goals.set_savings_goal(&s.user, &800, &None);
+    let new_id = s.goal().id;
+    assert_ne!(new_id, goal_id);
+    assert_eq!(s.goal().invested_shares, 300);
+    assert_eq!(
+        s.goals.try_sweep_to_defindex(&goal_id),
+        Err(Ok(Error::GoalNotFound))
+    );
+    assert_eq!(
+        s.goals.try_sweep_to_defindex(&(new_id + 1)),
+        Err(Ok(Error::GoalNotFound))
+    );
+
+    // Retirar rescata las shares de la meta y las saca del pool
+    assert_eq!(s.goals.withdraw_from_goal(&s.user, &300), 0);
+    assert_eq!(s.goals.pooled_shares(), 0);
+    assert_eq!(s.token.balance(&s.user), 1_000);
+}
+
+#[test]
+fn pool_cap_limits_what_goals_invest() {
+    let s = Setup::new();
+    s.goals.set_pool_cap(&s.admin, &100);
+    assert_eq!(
+        s.goals.try_set_pool_cap(&s.admin, &-1),
+        Err(Ok(Error::InvalidAmount))
+    );
+    s.goals.set_auto_sweep(&s.user, &Some(0));
+
+    // Dentro del tope se invierte todo
+    assert_eq!(s.goals.deposit_to_goal(&s.user, &80), 0);
+    assert_eq!(s.goals.pooled_shares(), 80);
+
+    // Otro usuario comparte el mismo pool: lo que pase el tope se queda guardado
+    let other = Address::generate(&s.env);
+    StellarAssetClient::new(&s.env, &s.token.address).mint(&other, &100);
+    s.goals.set_savings_goal(&other, &500, &None);
+    s.goals.set_auto_sweep(&other, &Some(0));
+    assert_eq!(s.goals.deposit_to_goal(&other, &50), 50);
+    assert_eq!(s.goals.get_savings_goal(&other).unwrap().invested_shares, 0);
+    assert_eq!(s.goals.pooled_shares(), 80);
+    assert_eq!(s.defindex.shares(&s.goals.address), 80);
+
+    // Con tope 0 no se invierte nada
+    s.goals.set_pool_cap(&s.admin, &0);
+    assert_eq!(s.goals.deposit_to_goal(&s.user, &10), 10);
+    assert_eq!(s.goals.pooled_shares(), 80);
+}
@@ -2,7 +2,8 @@
 extern crate alloc;
 
 use soroban_sdk::{
-    contract, contracterror, contractimpl, symbol_short, Bytes, BytesN, Env, Symbol,
+    contract, contracterror, contractimpl, contracttype, symbol_short, Address, Bytes, BytesN,
+    Env, Symbol, Vec,
 };
 
 #[contracterror]
@@ -12,6 +13,113 @@ pub enum Error {
     VkNotSet = 1,
     InvalidProofFormat = 2,
     VerificationFailed = 3,
+    PublicInputCountMismatch = 4,
+    ProofAlreadyUsed = 5,
+    NotAuthorized = 6,
+    AlreadyInitialized = 7,
+    ConsumerNotAllowed = 8,
+    PublicInputMismatch = 9,
+}
+
+/// Resultado de una verificación: proof_id y los public inputs del proof
+/// (campos de 32 bytes big-endian, en el orden del circuito).
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VerificationResult {
+    pub proof_id: BytesN<32>,
+    pub public_inputs: Vec<BytesN<32>>,
+}
+
+/// Llaves de persistent storage para los registros por proof
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DataKey {
+    Verified(BytesN<32>),
+    Consumed(BytesN<32>),
+}
+
+/// Se escribe una vez por proof_id en su primera verificación.
+/// `submitter` solo se conoce cuando el proof llega por `consume_proof`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VerificationRecord {
+    pub verified_at: u64,
+    pub vk_hash: BytesN<32>,
+    pub submitter: Option<Address>,
+}
+
+/// Registro (nullifier) que escribe `consume_proof`
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ProofConsumption {
+    pub caller: Address,
+    pub context: BytesN<32>,
+    pub consumed_at: u64,
+}
+
+/// Los registros por proof se extienden a ~30 días (ledgers de 5 s) cada vez
+/// que se escriben o se vuelven a tocar con menos de ~15 días restantes
+const RECORD_TTL_THRESHOLD: u32 = 259_200;
+const RECORD_TTL_EXTEND_TO: u32 = 518_400;
+
+/// Dígitos de un campo hex de `vk_fields.json` (`0x` + 1 a 64 dígitos hex)
+fn hex_field_digits(field: &[u8]) -> Option<&[u8]> {
+    let hex = field.strip_prefix(b"0x").unwrap_or(field);
+    if hex.is_empty() || hex.len() > 64 || !hex.iter().all(u8::is_ascii_hexdigit) {
+        return None;
+    }
+    Some(hex)
+}
+
+/// Valor de un campo del header, que debe caber en u64
+fn header_field_u64(field: &[u8]) -> Option<u64> {
+    let hex = hex_field_digits(field)?;
+    let (high, low) = hex.split_at(hex.len().saturating_sub(16));
+    if high.iter().any(|ch| *ch != b'0') {
+        return None;
+    }
+    let mut out: u64 = 0;
+    for &ch in low {
+        out = (out << 4) | (ch as char).to_digit(16)? as u64;
+    }
+    Some(out)
+}
+
+/// Lee `public_inputs_size` (campo [1]) de un `vk_fields.json`.
+/// Parsea el arreglo completo de strings hex; retorna None si el VK no tiene
+/// ese formato (ej. el VK por defecto `{}`) o si el campo no cabe en u64.
+fn vk_public_inputs_size(vk_json: &[u8]) -> Option<u64> {
+    fn trim(s: &[u8]) -> &[u8] {
+        let start = s
+            .iter()
+            .position(|b| !b.is_ascii_whitespace())
+            .unwrap_or(s.len());
+        &s[start..]
+    }
+
+    let mut rest = trim(trim(vk_json).strip_prefix(b"[")?);
+    let mut public_inputs_size = None;
+    let mut index = 0usize;
+    while !rest.starts_with(b"]") {
+        if index > 0 {
+            rest = trim(rest.strip_prefix(b",")?);
+        }
+        rest = rest.strip_prefix(b"\"")?;
+        let end = rest.iter().position(|b| *b == b'"')?;
+        let field = &rest[..end];
+        if index == 1 {
+            public_inputs_size = Some(header_field_u64(field)?);
+        } else {
+            hex_field_digits(field)?;
+        }
+        rest = trim(&rest[end + 1..]);
+        index += 1;
+    }
+
+    if !trim(&rest[1..]).is_empty() {
+        return None;
+    }
+    public_inputs_size
 }
 
 #[contract]
@@ -19,8 +127,50 @@ pub struct SimpleVerifier;
 
 #[contractimpl]
 impl SimpleVerifier {
-    /// Set verification key (simplified - solo guarda el hash para referencia)
-    pub fn set_vk(env: Env, vk_json: Bytes) -> Result<BytesN<32>, Error> {
+    /// Define el admin que administra los consumidores. Se llama una vez, al desplegar
+    pub fn initialize(env: Env, admin: Address) -> Result<(), Error> {
+        admin.require_auth();
+        if env.storage().instance().has(&Self::key_admin()) {
+            return Err(Error::AlreadyInitialized);
+        }
+        env.storage().instance().set(&Self::key_admin(), &admin);
+        Ok(())
+    }
+
+    /// Admin actual, si ya se inicializó
+    pub fn get_admin(env: Env) -> Option<Address> {
+        env.storage().instance().get(&Self::key_admin())
+    }
+
+    /// Permite a `consumer` (ej. savings-goals) llamar `consume_proof`. Solo admin
+    pub fn add_consumer(env: Env, caller: Address, consumer: Address) -> Result<(), Error> {
+        Self::assert_admin(&env, &caller)?;
+        env.storage()
+            .instance()
+            .set(&Self::key_consumer(&consumer), &true);
+        Ok(())
+    }
+
+    /// Quita a `consumer` el acceso a `consume_proof`. Solo admin
+    pub fn remove_consumer(env: Env, caller: Address, consumer: Address) -> Result<(), Error> {
+        Self::assert_admin(&env, &caller)?;
+        env.storage()
+            .instance()
+            .remove(&Self::key_consumer(&consumer));
+        Ok(())
+    }
+
+    /// Indica si `consumer` puede llamar `consume_proof`
+    pub fn is_consumer(env: Env, consumer: Address) -> bool {
+        env.storage()
+            .instance()
+            .has(&Self::key_consumer(&consumer))
+    }
+
+    /// Set verification key (simplified - solo guarda el hash para referencia).
+    /// Solo admin
+    pub fn set_vk(env: Env, caller: Address, vk_json: Bytes) -> Result<BytesN<32>, Error> {
+        Self::assert_admin(&env, &caller)?;
         env.storage().instance().set(&Self::key_vk(), &vk_json);
         let hash: BytesN<32> = env.crypto().keccak256(&vk_json).into();
         env.storage().instance().set(&Self::key_vk_hash(), &hash);
@@ -32,7 +182,7 @@ impl SimpleVerifier {
     /// Esta es una versión simplificada que:
     /// - Valida el formato básico del proof_blob
     /// - Calcula el proof_id (keccak256 del blob)
-    /// - Guarda el proof_id para evitar duplicados
+    /// - Guarda el proof_id y rechaza con `ProofAlreadyUsed` un blob ya visto
     /// 
     /// ⚠️ NOTA: Esta versión NO hace verificación criptográfica completa.
     /// Para producción, usar el verificador Ultrahonk completo.
@@ -40,18 +190,25 @@ impl SimpleVerifier {
         env: Env,
         proof_blob: Bytes,
     ) -> Result<BytesN<32>, Error> {
-        // En desarrollo, el VK es opcional (solo se usa para referencia)
-        // Si no está configurado, usamos un VK por defecto
-        let _vk_json: Bytes = env
-            .storage()
+        let vk_json = Self::stored_vk(&env);
+        Self::verify_with_vk(&env, &vk_json, &proof_blob, None)
+    }
+
+    /// VK guardado con `set_vk`. En desarrollo el VK es opcional (solo se usa
+    /// para referencia): si no está configurado se usa `{}`, sin guardarlo
+    fn stored_vk(env: &Env) -> Bytes {
+        env.storage()
             .instance()
             .get(&Self::key_vk())
-            .unwrap_or_else(|| {
-                // VK por defecto para desarrollo
-                let default_vk = Bytes::from_slice(&env, b"{}");
-                env.storage().instance().set(&Self::key_vk(), &default_vk);
-                default_vk
-            });
+            .unwrap_or_else(|| Bytes::from_slice(env, b"{}"))
+    }
+
+    fn verify_with_vk(
+        env: &Env,
+        vk_json: &Bytes,
+        proof_blob: &Bytes,
+        submitter: Option<Address>,
+    ) -> Result<BytesN<32>, Error> {
 
         // Validar formato básico del proof_blob
         // Formato esperado: [4-byte count][public_inputs][proof]
@@ -70,49 +227,263 @@ impl SimpleVerifier {
 
         // Validar que haya suficientes bytes
         // Mínimo: 4 bytes (count) + count * 32 bytes (public inputs) + algunos bytes de proof
-        let min_size = 4 + (count as usize * 32) + 100; // Al menos 100 bytes de proof
+        let min_size = (count as usize)
+            .checked_mul(32)
+            .and_then(|inputs| inputs.checked_add(4 + 100)) // Al menos 100 bytes de proof
+            .ok_or(Error::InvalidProofFormat)?;
         if blob_vec.len() < min_size {
             return Err(Error::InvalidProofFormat);
         }
 
         // Calcular proof_id (keccak256 del blob completo)
-        let proof_id: BytesN<32> = env.crypto().keccak256(&proof_blob).into();
+        let proof_id: BytesN<32> = env.crypto().keccak256(proof_blob).into();
 
         // Verificar si este proof ya fue verificado antes
-        if env
-            .storage()
-            .instance()
-            .get(&proof_id)
-            .unwrap_or(false)
-        {
-            // Ya fue verificado, retornar el mismo ID
+        let key = DataKey::Verified(proof_id.clone());
+        let existing: Option<VerificationRecord> = env.storage().persistent().get(&key);
+        if let Some(mut record) = existing {
+            // Sin verificación criptográfica, un blob ya visto es un replay.
+            // `consume_proof` (submitter conocido) tiene su propio nullifier:
+            // ahí se acepta y se completa el submitter, para que verificar
+            // antes no permita quemar el proof de otro.
+            if submitter.is_none() {
+                return Err(Error::ProofAlreadyUsed);
+            }
+            if record.submitter.is_none() {
+                record.submitter = submitter;
+                env.storage().persistent().set(&key, &record);
+            }
+            Self::extend_record(env, &key);
             return Ok(proof_id);
         }
 
         // ⚠️ VERSIÓN SIMPLIFICADA: Solo validamos formato, no la verificación criptográfica
         // En producción, aquí se haría la verificación real usando el VK
-        
-        // Guardar que este proof fue "verificado" (en desarrollo, aceptamos cualquier proof con formato válido)
-        env.storage().instance().set(&proof_id, &true);
+
+        // Guardar que este proof fue "verificado" (en desarrollo, aceptamos cualquier proof con formato válido).
+        // Va en persistent storage: instance storage se carga completo en cada llamada
+        let record = VerificationRecord {
+            verified_at: env.ledger().timestamp(),
+            vk_hash: env.crypto().keccak256(vk_json).into(),
+            submitter,
+        };
+        env.storage().persistent().set(&key, &record);
+        Self::extend_record(env, &key);
 
         Ok(proof_id)
     }
 
-    /// Verify proof with explicit VK (para compatibilidad)
+    /// Verifica con el VK almacenado y retorna el proof_id junto con los public inputs.
+    /// Si el VK es un `vk_fields.json`, el número de inputs debe coincidir con su
+    /// `public_inputs_size` (o con ese valor menos los 16 pairing points de bb recientes).
+    /// `circuit_id` existe por compatibilidad con el verificador Ultrahonk: aquí
+    /// hay un solo VK y se ignora.
+    pub fn verify_and_get_public_inputs(
+        env: Env,
+        _circuit_id: Option<Symbol>,
+        proof_blob: Bytes,
+    ) -> Result<VerificationResult, Error> {
+        let vk_json = Self::stored_vk(&env);
+        let proof_id = Self::verify_with_vk(&env, &vk_json, &proof_blob, None)?;
+        let public_inputs = Self::public_inputs(&env, &vk_json, &proof_blob)?;
+
+        Ok(VerificationResult {
+            proof_id,
+            public_inputs,
+        })
+    }
+
+    /// Verifica con el VK almacenado y liga el proof al estado on-chain: los
+    /// public inputs deben ser iguales a `expected` posición por posición, salvo
+    /// las posiciones en `wildcards` (ej. outputs del circuito). `circuit_id` se
+    /// ignora, como en `verify_and_get_public_inputs`. Retorna el proof_id
+    pub fn verify_with_expected_inputs(
+        env: Env,
+        _circuit_id: Option<Symbol>,
+        proof_blob: Bytes,
+        expected: Vec<BytesN<32>>,
+        wildcards: Vec<u32>,
+    ) -> Result<BytesN<32>, Error> {
+        let vk_json = Self::stored_vk(&env);
+        let proof_id = Self::verify_with_vk(&env, &vk_json, &proof_blob, None)?;
+        let public_inputs = Self::public_inputs(&env, &vk_json, &proof_blob)?;
+        Self::check_expected_inputs(&public_inputs, &expected, &wildcards)?;
+        Ok(proof_id)
+    }
+
+    /// Public inputs de un blob con formato ya validado. Si el VK es un
+    /// `vk_fields.json`, su número debe coincidir con `public_inputs_size` (o
+    /// con ese valor menos los 16 pairing points de bb recientes)
+    fn public_inputs(
+        env: &Env,
+        vk_json: &Bytes,
+        proof_blob: &Bytes,
+    ) -> Result<Vec<BytesN<32>>, Error> {
+        let blob_vec = proof_blob.to_alloc_vec();
+        let count = u32::from_be_bytes([blob_vec[0], blob_vec[1], blob_vec[2], blob_vec[3]]);
+
+        if let Some(expected) = vk_public_inputs_size(&vk_json.to_alloc_vec()) {
+            let count = count as u64;
+            if count != expected && count + 16 != expected {
+                return Err(Error::PublicInputCountMismatch);
+            }
+        }
+
+        let mut public_inputs = Vec::new(env);
+        for i in 0..count as usize {
+            let start = 4 + i * 32;
+            let mut field = [0u8; 32];
+            field.copy_from_slice(&blob_vec[start..start + 32]);
+            public_inputs.push_back(BytesN::from_array(env, &field));
+        }
+        Ok(public_inputs)
+    }
+
+    /// Falla con `PublicInputMismatch` si `public_inputs` no coincide con
+    /// `expected` fuera de las posiciones en `wildcards`
+    fn check_expected_inputs(
+        public_inputs: &Vec<BytesN<32>>,
+        expected: &Vec<BytesN<32>>,
+        wildcards: &Vec<u32>,
+    ) -> Result<(), Error> {
+        if public_inputs.len() != expected.len() {
+            return Err(Error::PublicInputMismatch);
+        }
+        for (i, actual) in public_inputs.iter().enumerate() {
+            let i = i as u32;
+            if !wildcards.contains(i) && Some(actual) != expected.get(i) {
+                return Err(Error::PublicInputMismatch);
+            }
+        }
+        Ok(())
+    }
+
+    /// Verifica con el VK almacenado y consume el proof: registra
+    /// (proof_id, caller, context) y falla con `ProofAlreadyUsed` si ya se usó,
+    /// sin importar el caller o el contexto.
+    /// `caller` es el contrato que consume y debe estar permitido con `add_consumer`;
+    /// `context` es, por ejemplo, el id de la meta o del curso. `circuit_id` se
+    /// ignora, como en `verify_and_get_public_inputs`. Los public inputs se
+    /// comparan con `expected` como en `verify_with_expected_inputs`.
+    /// Retorna el proof_id y los public inputs.
+    pub fn consume_proof(
+        env: Env,
+        caller: Address,
+        _circuit_id: Option<Symbol>,
+        proof_blob: Bytes,
+        context: BytesN<32>,
+        expected: Vec<BytesN<32>>,
+        wildcards: Vec<u32>,
+    ) -> Result<VerificationResult, Error> {
+        caller.require_auth();
+        if !Self::is_consumer(env.clone(), caller.clone()) {
+            return Err(Error::ConsumerNotAllowed);
+        }
+
+        let proof_id: BytesN<32> = env.crypto().keccak256(&proof_blob).into();
+        if env.storage().persistent().has(&DataKey::Consumed(proof_id.clone())) {
+            return Err(Error::ProofAlreadyUsed);
+        }
+
+        let vk_json = Self::stored_vk(&env);
+        Self::verify_with_vk(&env, &vk_json, &proof_blob, Some(caller.clone()))?;
+        let public_inputs = Self::public_inputs(&env, &vk_json, &proof_blob)?;
+        Self::check_expected_inputs(&public_inputs, &expected, &wildcards)?;
+
+        let record = ProofConsumption {
+            caller: caller.clone(),
+            context: context.clone(),
+            consumed_at: env.ledger().timestamp(),
+        };
+        let key = DataKey::Consumed(proof_id.clone());
+        env.storage().persistent().set(&key, &record);
+        Self::extend_record(&env, &key);
+
+        env.events()
+            .publish((symbol_short!("consumed"), caller, context), proof_id.clone());
+
+        Ok(VerificationResult {
+            proof_id,
+            public_inputs,
+        })
+    }
+
+    /// Quién consumió `proof_id` y en qué contexto
+    pub fn get_proof_consumption(env: Env, proof_id: BytesN<32>) -> Option<ProofConsumption> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Consumed(proof_id.clone()))
+    }
+
+    /// Verify proof with explicit VK (para compatibilidad). El VK no se guarda:
+    /// solo queda su hash en el registro de verificación
     pub fn verify_proof(env: Env, vk_json: Bytes, proof_blob: Bytes) -> Result<BytesN<32>, Error> {
-        // Guardar VK temporalmente
-        env.storage().instance().set(&Self::key_vk(), &vk_json);
-        
-        // Usar la función con VK almacenado
-        Self::verify_proof_with_stored_vk(env, proof_blob)
+        Self::verify_with_vk(&env, &vk_json, &proof_blob, None)
+    }
+
+    /// Obtiene el registro de verificación de un proof_id
+    pub fn get_verification(env: Env, proof_id: BytesN<32>) -> Option<VerificationRecord> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Verified(proof_id))
     }
 
     /// Check if a proof_id was previously verified
+    /// (incluye el flag antiguo en instance storage de proofs previos a la migración)
     pub fn is_verified(env: Env, proof_id: BytesN<32>) -> bool {
         env.storage()
+            .persistent()
+            .has(&DataKey::Verified(proof_id.clone()))
+            || env
+                .storage()
+                .instance()
+                .get(&proof_id)
+                .unwrap_or(false)
+    }
+
+    /// Mueve los flags antiguos de instance storage (`proof_id → true`, de antes
+    /// de que los registros pasaran a persistent storage) a registros
+    /// `DataKey::Verified` y los borra. Las llaves de instance no se pueden
+    /// listar, así que el admin pasa los proof_ids (ej. de eventos pasados). Los
+    /// flags no guardaban fecha ni VK: el registro lleva la fecha de la migración
+    /// y el vk_hash actual. Solo admin. Retorna cuántos flags se migraron
+    pub fn migrate_legacy_proofs(
+        env: Env,
+        caller: Address,
+        proof_ids: Vec<BytesN<32>>,
+    ) -> Result<u32, Error> {
+        Self::assert_admin(&env, &caller)?;
+        let vk_hash: BytesN<32> = env
+            .storage()
             .instance()
-            .get(&proof_id)
-            .unwrap_or(false)
+            .get(&Self::key_vk_hash())
+            .unwrap_or_else(|| BytesN::from_array(&env, &[0u8; 32]));
+
+        let mut migrated = 0u32;
+        for proof_id in proof_ids.iter() {
+            if !env.storage().instance().get(&proof_id).unwrap_or(false) {
+                continue;
+            }
+            let key = DataKey::Verified(proof_id.clone());
+            if !env.storage().persistent().has(&key) {
+                let record = VerificationRecord {
+                    verified_at: env.ledger().timestamp(),
+                    vk_hash: vk_hash.clone(),
+                    submitter: None,
+                };
+                env.storage().persistent().set(&key, &record);
+            }
+            Self::extend_record(&env, &key);
+            env.storage().instance().remove(&proof_id);
+            migrated += 1;
+        }
+        Ok(migrated)
+    }
+
+    fn extend_record(env: &Env, key: &DataKey) {
+        env.storage()
+            .persistent()
+            .extend_ttl(key, RECORD_TTL_THRESHOLD, RECORD_TTL_EXTEND_TO);
     }
 
     // Helpers para storage keys
@@ -123,5 +494,25 @@ impl SimpleVerifier {
     fn key_vk_hash() -> Symbol {
         symbol_short!("vkh")
     }
+
+    fn key_admin() -> Symbol {
+        symbol_short!("admin")
+    }
+
+    fn key_consumer(consumer: &Address) -> (Symbol, Address) {
+        (symbol_short!("consumer"), consumer.clone())
+    }
+
+    /// `caller` debe ser el admin guardado por `initialize`
+    fn assert_admin(env: &Env, caller: &Address) -> Result<(), Error> {
+        caller.require_auth();
+        let admin: Option<Address> = env.storage().instance().get(&Self::key_admin());
+        if admin.as_ref() != Some(caller) {
+            return Err(Error::NotAuthorized);
+        }
+        Ok(())
+    }
 }
 
+
+mod test;
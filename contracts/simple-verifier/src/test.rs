@@ -0,0 +1,398 @@
+#![cfg(test)]
+extern crate std;
+
+use super::*;
+use soroban_sdk::{
+    testutils::{storage::Persistent as _, Address as _},
+    vec,
+};
+
+fn setup(env: &Env) -> SimpleVerifierClient<'_> {
+    env.mock_all_auths();
+    SimpleVerifierClient::new(env, &env.register(SimpleVerifier, ()))
+}
+
+/// Verificador inicializado con un consumidor permitido: (client, admin, consumer)
+fn setup_with_consumer(env: &Env) -> (SimpleVerifierClient<'_>, Address, Address) {
+    let client = setup(env);
+    let admin = Address::generate(env);
+    let consumer = Address::generate(env);
+    client.initialize(&admin);
+    client.add_consumer(&admin, &consumer);
+    (client, admin, consumer)
+}
+
+/// Proof blob [count][public inputs][proof] con `count` inputs en cero
+fn blob(env: &Env, count: u32, inputs: u32) -> Bytes {
+    let mut blob = Bytes::from_array(env, &count.to_be_bytes());
+    for _ in 0..inputs {
+        blob.extend_from_array(&[0u8; 32]);
+    }
+    blob.extend_from_array(&[7u8; 100]);
+    blob
+}
+
+/// Campo de 32 bytes big-endian con el valor `value`
+fn field(env: &Env, value: u64) -> BytesN<32> {
+    let mut field = [0u8; 32];
+    field[24..].copy_from_slice(&value.to_be_bytes());
+    BytesN::from_array(env, &field)
+}
+
+/// `count` public inputs en cero, como los de `blob`
+fn zeros(env: &Env, count: u32) -> Vec<BytesN<32>> {
+    let mut out = Vec::new(env);
+    for _ in 0..count {
+        out.push_back(field(env, 0));
+    }
+    out
+}
+
+/// Proof blob [count][public inputs][proof] con los valores de `inputs`
+fn blob_with(env: &Env, inputs: &[u64]) -> Bytes {
+    let mut blob = Bytes::from_array(env, &(inputs.len() as u32).to_be_bytes());
+    for input in inputs {
+        blob.extend_from_array(&field(env, *input).to_array());
+    }
+    blob.extend_from_array(&[7u8; 100]);
+    blob
+}
+
+#[test]
+fn huge_input_count_is_a_format_error() {
+    let env = Env::default();
+    let client = setup(&env);
+
+    assert_eq!(
+        client.try_verify_proof_with_stored_vk(&blob(&env, u32::MAX, 1)),
+        Err(Ok(Error::InvalidProofFormat))
+    );
+    assert_eq!(
+        client.try_verify_proof_with_stored_vk(&blob(&env, 2, 1)),
+        Err(Ok(Error::InvalidProofFormat))
+    );
+}
+
+#[test]
+fn reads_public_inputs_size_from_vk_fields() {
+    assert_eq!(
+        vk_public_inputs_size(b" [\"0x0c\", \"0x0000000000000000000000000000000000000000000000000000000000000012\",\"0x1\"]\n"),
+        Some(18)
+    );
+
+    // No es un arreglo de campos hex
+    assert_eq!(vk_public_inputs_size(b"{}"), None);
+    assert_eq!(vk_public_inputs_size(b"[]"), None);
+    assert_eq!(
+        vk_public_inputs_size(b"{\"a\":\"0x1\",\"b\":\"0x12\"}"),
+        None
+    );
+    assert_eq!(vk_public_inputs_size(b"[\"0x1\",\"0x12\""), None);
+    assert_eq!(vk_public_inputs_size(b"[\"0x1\",\"0x12\",\"zz\"]"), None);
+    assert_eq!(vk_public_inputs_size(b"[\"0x1\",\"0x12\"] x"), None);
+    // No cabe en u64
+    assert_eq!(
+        vk_public_inputs_size(b"[\"0x1\",\"0x10000000000000000\"]"),
+        None
+    );
+}
+
+#[test]
+fn public_input_count_must_match_the_vk() {
+    let env = Env::default();
+    let (client, admin, _) = setup_with_consumer(&env);
+    client.set_vk(
+        &admin,
+        &Bytes::from_slice(&env, b"[\"0xc\",\"0x12\",\"0x1\"]"),
+    );
+
+    // 18 inputs, o 18 - 16 pairing points
+    assert_eq!(
+        client
+            .verify_and_get_public_inputs(&None, &blob(&env, 2, 2))
+            .public_inputs
+            .len(),
+        2
+    );
+    assert_eq!(
+        client.try_verify_and_get_public_inputs(&None, &blob(&env, 3, 3)),
+        Err(Ok(Error::PublicInputCountMismatch))
+    );
+}
+
+#[test]
+fn set_vk_is_admin_only() {
+    let env = Env::default();
+    let (client, admin, _) = setup_with_consumer(&env);
+    let vk = Bytes::from_slice(&env, b"[\"0xc\",\"0x12\",\"0x1\"]");
+
+    assert_eq!(
+        client.try_set_vk(&Address::generate(&env), &vk),
+        Err(Ok(Error::NotAuthorized))
+    );
+    let vk_hash: BytesN<32> = env.crypto().keccak256(&vk).into();
+    assert_eq!(client.set_vk(&admin, &vk), vk_hash);
+
+    // Sin initialize nadie puede configurar el VK
+    let fresh = setup(&env);
+    assert_eq!(fresh.try_set_vk(&admin, &vk), Err(Ok(Error::NotAuthorized)));
+}
+
+#[test]
+fn verify_proof_keeps_the_stored_vk() {
+    let env = Env::default();
+    let (client, admin, _) = setup_with_consumer(&env);
+    client.set_vk(
+        &admin,
+        &Bytes::from_slice(&env, b"[\"0xc\",\"0x12\",\"0x1\"]"),
+    );
+
+    // Un VK de 3 inputs pasado a verify_proof no reemplaza el guardado
+    let other_vk = Bytes::from_slice(&env, b"[\"0xc\",\"0x3\",\"0x1\"]");
+    let proof_id = client.verify_proof(&other_vk, &blob(&env, 3, 3));
+    let other_hash: BytesN<32> = env.crypto().keccak256(&other_vk).into();
+    assert_eq!(
+        client.get_verification(&proof_id).unwrap().vk_hash,
+        other_hash
+    );
+    assert_eq!(
+        client.try_verify_and_get_public_inputs(&None, &blob(&env, 3, 4)),
+        Err(Ok(Error::PublicInputCountMismatch))
+    );
+    assert_eq!(
+        client
+            .verify_and_get_public_inputs(&None, &blob(&env, 2, 3))
+            .public_inputs
+            .len(),
+        2
+    );
+}
+
+#[test]
+fn replayed_blob_is_rejected() {
+    let env = Env::default();
+    let (client, _, consumer) = setup_with_consumer(&env);
+    let blob = blob(&env, 1, 1);
+    let context = BytesN::from_array(&env, &[1u8; 32]);
+
+    let proof_id = client.verify_proof_with_stored_vk(&blob);
+    assert_eq!(
+        client.try_verify_proof_with_stored_vk(&blob),
+        Err(Ok(Error::ProofAlreadyUsed))
+    );
+    assert_eq!(
+        client.try_verify_and_get_public_inputs(&None, &blob),
+        Err(Ok(Error::ProofAlreadyUsed))
+    );
+
+    // Verificarlo antes no impide consumirlo, pero solo una vez
+    assert_eq!(
+        client
+            .consume_proof(
+                &consumer,
+                &None,
+                &blob,
+                &context,
+                &zeros(&env, 1),
+                &vec![&env]
+            )
+            .proof_id,
+        proof_id
+    );
+    assert_eq!(
+        client.try_consume_proof(
+            &consumer,
+            &None,
+            &blob,
+            &context,
+            &zeros(&env, 1),
+            &vec![&env]
+        ),
+        Err(Ok(Error::ProofAlreadyUsed))
+    );
+    let record = client.get_verification(&proof_id).unwrap();
+    assert_eq!(record.submitter, Some(consumer));
+}
+
+#[test]
+fn consume_proof_is_limited_to_allowed_consumers() {
+    let env = Env::default();
+    let (client, admin, consumer) = setup_with_consumer(&env);
+    let outsider = Address::generate(&env);
+    let context = BytesN::from_array(&env, &[1u8; 32]);
+
+    assert_eq!(
+        client.try_initialize(&outsider),
+        Err(Ok(Error::AlreadyInitialized))
+    );
+    assert_eq!(
+        client.try_add_consumer(&outsider, &outsider),
+        Err(Ok(Error::NotAuthorized))
+    );
+    assert_eq!(
+        client.try_consume_proof(
+            &outsider,
+            &None,
+            &blob(&env, 1, 1),
+            &context,
+            &zeros(&env, 1),
+            &vec![&env]
+        ),
+        Err(Ok(Error::ConsumerNotAllowed))
+    );
+
+    client.remove_consumer(&admin, &consumer);
+    assert!(!client.is_consumer(&consumer));
+    assert_eq!(
+        client.try_consume_proof(
+            &consumer,
+            &None,
+            &blob(&env, 1, 1),
+            &context,
+            &zeros(&env, 1),
+            &vec![&env]
+        ),
+        Err(Ok(Error::ConsumerNotAllowed))
+    );
+}
+
+#[test]
+fn legacy_proof_flags_migrate_to_persistent_records() {
+    let env = Env::default();
+    let (client, admin, _) = setup_with_consumer(&env);
+    let vk_hash = client.set_vk(
+        &admin,
+        &Bytes::from_slice(&env, b"[\"0xc\",\"0x1\",\"0x1\"]"),
+    );
+    let legacy = BytesN::from_array(&env, &[1u8; 32]);
+    let unknown = BytesN::from_array(&env, &[2u8; 32]);
+    env.as_contract(&client.address, || {
+        env.storage().instance().set(&legacy, &true)
+    });
+    let ids = soroban_sdk::vec![&env, legacy.clone(), unknown.clone()];
+
+    assert_eq!(
+        client.try_migrate_legacy_proofs(&Address::generate(&env), &ids),
+        Err(Ok(Error::NotAuthorized))
+    );
+    assert_eq!(client.migrate_legacy_proofs(&admin, &ids), 1);
+
+    let record = client.get_verification(&legacy).unwrap();
+    assert_eq!(record.vk_hash, vk_hash);
+    assert!(client.is_verified(&legacy));
+    assert!(client.get_verification(&unknown).is_none());
+    env.as_contract(&client.address, || {
+        assert!(!env.storage().instance().has(&legacy));
+        assert_eq!(
+            env.storage()
+                .persistent()
+                .get_ttl(&DataKey::Verified(legacy.clone())),
+            RECORD_TTL_EXTEND_TO
+        );
+    });
+
+    // Ya migrado: no queda nada por mover
+    assert_eq!(client.migrate_legacy_proofs(&admin, &ids), 0);
+}
+
+#[test]
+fn proof_records_get_their_ttl_extended() {
+    let env = Env::default();
+    let (client, _, consumer) = setup_with_consumer(&env);
+    let context = BytesN::from_array(&env, &[1u8; 32]);
+
+    let proof_id = client
+        .consume_proof(
+            &consumer,
+            &None,
+            &blob(&env, 1, 1),
+            &context,
+            &zeros(&env, 1),
+            &vec![&env],
+        )
+        .proof_id;
+    env.as_contract(&client.address, || {
+        let persistent = env.storage().persistent();
+        for key in [
+            DataKey::Verified(proof_id.clone()),
+            DataKey::Consumed(proof_id.clone()),
+        ] {
+            assert_eq!(persistent.get_ttl(&key), RECORD_TTL_EXTEND_TO);
+        }
+    });
+}
+
+#[test]
+fn expected_inputs_bind_the_proof() {
+    let env = Env::default();
+    let (client, admin, _) = setup_with_consumer(&env);
+    client.set_vk(
+        &admin,
+        &Bytes::from_slice(&env, b"[\"0xc\",\"0x3\",\"0x1\"]"),
+    );
+    let expected = vec![&env, field(&env, 18), field(&env, 2), field(&env, 0)];
+    let verify = |inputs: &[u64], expected: &Vec<BytesN<32>>, wildcards: &Vec<u32>| {
+        client.try_verify_with_expected_inputs(&None, &blob_with(&env, inputs), expected, wildcards)
+    };
+
+    // La posición 2 es un output: sin wildcard también se compara
+    assert_eq!(
+        verify(&[18, 2, 1], &expected, &vec![&env]),
+        Err(Ok(Error::PublicInputMismatch))
+    );
+    assert!(verify(&[18, 2, 1], &expected, &vec![&env, 2]).is_ok());
+    assert_eq!(
+        verify(&[17, 2, 1], &expected, &vec![&env, 2]),
+        Err(Ok(Error::PublicInputMismatch))
+    );
+
+    // Menos valores esperados que inputs
+    assert_eq!(
+        verify(&[18, 2, 2], &zeros(&env, 2), &vec![&env, 0, 1]),
+        Err(Ok(Error::PublicInputMismatch))
+    );
+    // El número de inputs también debe coincidir con el VK
+    assert_eq!(
+        verify(&[18, 2], &zeros(&env, 2), &vec![&env, 0, 1]),
+        Err(Ok(Error::PublicInputCountMismatch))
+    );
+}
+
+#[test]
+fn consume_proof_returns_the_matched_inputs() {
+    let env = Env::default();
+    let (client, _, consumer) = setup_with_consumer(&env);
+    let context = BytesN::from_array(&env, &[1u8; 32]);
+    let expected = vec![&env, field(&env, 42), field(&env, 0)];
+    let wildcards = vec![&env, 1];
+
+    // Un input distinto no consume el proof
+    let wrong = blob_with(&env, &[41, 3]);
+    assert_eq!(
+        client.try_consume_proof(&consumer, &None, &wrong, &context, &expected, &wildcards),
+        Err(Ok(Error::PublicInputMismatch))
+    );
+    assert!(client
+        .get_proof_consumption(&env.crypto().keccak256(&wrong).into())
+        .is_none());
+
+    let result = client.consume_proof(
+        &consumer,
+        &None,
+        &blob_with(&env, &[42, 3]),
+        &context,
+        &expected,
+        &wildcards,
+    );
+    assert_eq!(
+        result.public_inputs,
+        vec![&env, field(&env, 42), field(&env, 3)]
+    );
+    assert_eq!(
+        client
+            .get_proof_consumption(&result.proof_id)
+            .unwrap()
+            .context,
+        context
+    );
+}
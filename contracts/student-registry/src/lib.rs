@@ -0,0 +1,313 @@
+#![no_std]
+extern crate alloc;
+
+use soroban_sdk::{
+    contract, contracterror, contractimpl, symbol_short, xdr::ToXdr, Address, Bytes, BytesN, Env,
+    IntoVal, Symbol, Vec,
+};
+
+/// Vigencia máxima de una verificación (dos años)
+const MAX_VALIDITY_SECS: u64 = 2 * 365 * 24 * 60 * 60;
+
+/// Circuito de los proofs de verificación en el registro de VKs del verificador
+const USER_VERIFICATION_CIRCUIT: &str = "user_verification";
+
+/// Política de verificación de estudiantes.
+/// Los public inputs del proof (`min_age`, `min_semester`) deben ser >= a estos valores.
+#[derive(Clone, Debug)]
+#[soroban_sdk::contracttype]
+pub struct StudentPolicy {
+    pub min_age: u64,
+    pub min_semester: u64,
+    pub validity_secs: u64, // Vigencia de la verificación (ej. un semestre)
+}
+
+#[derive(Clone, Debug)]
+#[soroban_sdk::contracttype]
+pub struct StudentVerification {
+    pub proof_id: BytesN<32>,
+    pub verified_at: u64,
+    pub verified_until: u64,
+}
+
+/// Resultado de `consume_proof` en el verificador: proof_id y public inputs
+#[derive(Clone, Debug)]
+#[soroban_sdk::contracttype]
+pub struct VerificationResult {
+    pub proof_id: BytesN<32>,
+    pub public_inputs: Vec<BytesN<32>>,
+}
+
+#[contracterror]
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Error {
+    VerifierNotConfigured = 1,
+    PolicyNotConfigured = 2,
+    NotAuthorized = 3,
+    InvalidPublicInputs = 4,
+    PolicyNotMet = 5,
+    InvalidPolicy = 6,
+    ProofRejected = 7,
+    VerifierCallFailed = 8,
+    AlreadyInitialized = 9,
+}
+
+/// Registro de estatus de estudiante respaldado por el circuito `circuits/user-verification`.
+/// Otros contratos (DeFindex, recompensas de cursos) consultan `is_verified_student`.
+#[contract]
+pub struct StudentRegistry;
+
+#[contractimpl]
+impl StudentRegistry {
+    /// Inicializa el contrato con su administrador (una sola vez).
+    pub fn initialize(env: Env, admin: Address) -> Result<(), Error> {
+        if env.storage().instance().has(&Self::admin_key()) {
+            return Err(Error::AlreadyInitialized);
+        }
+        admin.require_auth();
+        env.storage().instance().set(&Self::admin_key(), &admin);
+        Ok(())
+    }
+
+    /// Configura el contrato verificador Ultrahonk (una sola dirección global).
+    pub fn set_verifier(env: Env, caller: Address, verifier: Address) {
+        Self::assert_admin(&env, &caller);
+        env.storage().instance().set(&Self::verifier_key(), &verifier);
+    }
+
+    /// Configura la política mínima de edad/semestre y la vigencia de la verificación
+    /// (entre 1 segundo y `MAX_VALIDITY_SECS`).
+    pub fn set_policy(
+        env: Env,
+        caller: Address,
+        min_age: u64,
+        min_semester: u64,
+        validity_secs: u64,
+    ) -> Result<(), Error> {
+        Self::assert_admin(&env, &caller);
+        if validity_secs == 0 || validity_secs > MAX_VALIDITY_SECS {
+            return Err(Error::InvalidPolicy);
+        }
+
+        let policy = StudentPolicy {
+            min_age,
+            min_semester,
+            validity_secs,
+        };
+        env.storage().instance().set(&Self::policy_key(), &policy);
+
+        Ok(())
+    }
+
+    /// Obtiene la política vigente.
+    pub fn get_policy(env: Env) -> Option<StudentPolicy> {
+        env.storage().instance().get(&Self::policy_key())
+    }
+
+    /// Envía un proof de `user-verification` y registra al usuario como estudiante verificado.
+    /// Public inputs esperados: [min_age, min_semester, user_hash, verification_code].
+    /// `user_hash` liga el proof a `user` (ver `user_hash`), así que no puede
+    /// reutilizarse para registrar otra cuenta. Retorna `verified_until`.
+    pub fn submit_verification(
+        env: Env,
+        user: Address,
+        proof_blob: Bytes,
+    ) -> Result<u64, Error> {
+        user.require_auth();
+
+        let policy: StudentPolicy = env
+            .storage()
+            .instance()
+            .get(&Self::policy_key())
+            .ok_or(Error::PolicyNotConfigured)?;
+
+        let verifier: Address = env
+            .storage()
+            .instance()
+            .get(&Self::verifier_key())
+            .ok_or(Error::VerifierNotConfigured)?;
+
+        // Public inputs [min_age, min_semester, user_hash, código]: el
+        // verificador exige el hash de `user`; el resto se valida abajo
+        let zero = BytesN::from_array(&env, &[0u8; 32]);
+        let expected = Vec::from_array(
+            &env,
+            [zero.clone(), zero.clone(), Self::user_hash(&env, &user), zero],
+        );
+        let wildcards = Vec::from_array(&env, [0, 1, 3]);
+        let result = Self::consume_proof(&env, &verifier, &user, proof_blob, expected, wildcards)?;
+
+        let min_age = Self::input_u64(&result.public_inputs, 0)?;
+        let min_semester = Self::input_u64(&result.public_inputs, 1)?;
+        let code = Self::input_u64(&result.public_inputs, 3)?;
+
+        if min_age < policy.min_age || min_semester < policy.min_semester {
+            return Err(Error::PolicyNotMet);
+        }
+        // El circuito retorna >= 1 cuando el estudiante está activo
+        if code == 0 {
+            return Err(Error::InvalidPublicInputs);
+        }
+
+        let now = env.ledger().timestamp();
+        let verification = StudentVerification {
+            proof_id: result.proof_id,
+            verified_at: now,
+            verified_until: now
+                .checked_add(policy.validity_secs)
+                .ok_or(Error::InvalidPolicy)?,
+        };
+
+        env.storage()
+            .persistent()
+            .set(&Self::verification_key(&user), &verification);
+
+        env.events().publish(
+            (symbol_short!("verified"), user),
+            verification.verified_until,
+        );
+
+        Ok(verification.verified_until)
+    }
+
+    /// Revoca la verificación de un usuario (solo admin).
+    pub fn revoke(env: Env, caller: Address, user: Address) {
+        Self::assert_admin(&env, &caller);
+        env.storage()
+            .persistent()
+            .remove(&Self::verification_key(&user));
+        env.events().publish((symbol_short!("revoked"), user), ());
+    }
+
+    /// Obtiene el registro de verificación de un usuario.
+    pub fn get_verification(env: Env, user: Address) -> Option<StudentVerification> {
+        env.storage()
+            .persistent()
+            .get(&Self::verification_key(&user))
+    }
+
+    /// Autoriza (o retira) un contrato para pasar el control de estudiante.
+    /// Pensado para contratos que depositan en nombre de estudiantes, como
+    /// `savings-goals` al barrer fondos hacia DeFindex.
+    pub fn set_allowed_contract(env: Env, caller: Address, contract: Address, allowed: bool) {
+        Self::assert_admin(&env, &caller);
+        let key = Self::allowed_contract_key(&contract);
+        if allowed {
+            env.storage().persistent().set(&key, &true);
+        } else {
+            env.storage().persistent().remove(&key);
+        }
+        env.events()
+            .publish((symbol_short!("allowed"), contract), allowed);
+    }
+
+    /// Indica si `contract` está en la lista de contratos autorizados.
+    pub fn is_allowed_contract(env: Env, contract: Address) -> bool {
+        env.storage()
+            .persistent()
+            .has(&Self::allowed_contract_key(&contract))
+    }
+
+    /// Indica si el usuario tiene una verificación de estudiante vigente.
+    /// Los contratos autorizados por el admin también cuentan como verificados.
+    pub fn is_verified_student(env: Env, user: Address) -> bool {
+        if Self::is_allowed_contract(env.clone(), user.clone()) {
+            return true;
+        }
+        Self::get_verification(env.clone(), user)
+            .map(|v| v.verified_until > env.ledger().timestamp())
+            .unwrap_or(false)
+    }
+
+    /// Verifica y consume el proof en el verificador, ligándolo a `user`. El
+    /// verificador compara los public inputs con `expected` salvo en las
+    /// posiciones de `wildcards` y retorna el proof_id y los public inputs.
+    /// Un proof inválido o ya usado se reporta como error en lugar de abortar.
+    fn consume_proof(
+        env: &Env,
+        verifier: &Address,
+        user: &Address,
+        proof_blob: Bytes,
+        expected: Vec<BytesN<32>>,
+        wildcards: Vec<u32>,
+    ) -> Result<VerificationResult, Error> {
+        let context: BytesN<32> = env.crypto().sha256(&user.clone().to_xdr(env)).into();
+
+        let mut args = Vec::new(env);
+        args.push_back(env.current_contract_address().into_val(env));
+        args.push_back(Some(Symbol::new(env, USER_VERIFICATION_CIRCUIT)).into_val(env));
+        args.push_back(proof_blob.into_val(env));
+        args.push_back(context.into_val(env));
+        args.push_back(expected.into_val(env));
+        args.push_back(wildcards.into_val(env));
+        let fn_symbol = Symbol::new(env, "consume_proof");
+
+        match env.try_invoke_contract::<VerificationResult, soroban_sdk::Error>(
+            verifier,
+            &fn_symbol,
+            args,
+        ) {
+            Ok(Ok(result)) => Ok(result),
+            Err(Ok(_)) => Err(Error::ProofRejected),
+            _ => Err(Error::VerifierCallFailed),
+        }
+    }
+
+    /// Hash de `user` que el circuito expone como public input: sha256 del XDR
+    /// de la dirección con el primer byte en cero, para que quepa en un campo BN254.
+    fn user_hash(env: &Env, user: &Address) -> BytesN<32> {
+        let mut hash = env.crypto().sha256(&user.clone().to_xdr(env)).to_array();
+        hash[0] = 0;
+        BytesN::from_array(env, &hash)
+    }
+
+    /// Public input `index` como u64: los 24 bytes altos deben ser cero
+    fn input_u64(public_inputs: &Vec<BytesN<32>>, index: u32) -> Result<u64, Error> {
+        let field = public_inputs
+            .get(index)
+            .ok_or(Error::InvalidPublicInputs)?
+            .to_array();
+        if field[..24].iter().any(|b| *b != 0) {
+            return Err(Error::InvalidPublicInputs);
+        }
+        let mut value = [0u8; 8];
+        value.copy_from_slice(&field[24..]);
+        Ok(u64::from_be_bytes(value))
+    }
+
+    fn verifier_key() -> Symbol {
+        symbol_short!("vrf")
+    }
+
+    fn policy_key() -> Symbol {
+        symbol_short!("policy")
+    }
+
+    fn verification_key(user: &Address) -> (Symbol, Address) {
+        (symbol_short!("student"), user.clone())
+    }
+
+    fn allowed_contract_key(contract: &Address) -> (Symbol, Address) {
+        (symbol_short!("allowed"), contract.clone())
+    }
+
+    fn admin_key() -> Symbol {
+        symbol_short!("admin")
+    }
+
+    fn assert_admin(env: &Env, caller: &Address) {
+        caller.require_auth();
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&Self::admin_key())
+            .unwrap_or_else(|| env.panic_with_error(Error::NotAuthorized));
+
+        if caller != &admin {
+            env.panic_with_error(Error::NotAuthorized);
+        }
+    }
+}
+
+mod test;
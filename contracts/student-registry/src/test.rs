@@ -0,0 +1,238 @@
+#![cfg(test)]
+extern crate std;
+
+use super::*;
+use soroban_sdk::{
+    contract, contracterror, contractimpl,
+    testutils::{Address as _, Ledger},
+};
+
+const VALIDITY: u64 = 180 * 24 * 60 * 60;
+
+#[contracterror]
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MockVerifierError {
+    ProofAlreadyUsed = 5,
+    PublicInputMismatch = 9,
+}
+
+/// Verificador mínimo: acepta una sola vez cualquier proof cuyos public inputs
+/// coincidan con `expected`
+#[contract]
+pub struct MockVerifier;
+
+#[contractimpl]
+impl MockVerifier {
+    pub fn consume_proof(
+        env: Env,
+        caller: Address,
+        _circuit_id: Option<Symbol>,
+        proof_blob: Bytes,
+        _context: BytesN<32>,
+        expected: Vec<BytesN<32>>,
+        wildcards: Vec<u32>,
+    ) -> Result<VerificationResult, MockVerifierError> {
+        caller.require_auth();
+        let proof_id: BytesN<32> = env.crypto().keccak256(&proof_blob).into();
+        if env.storage().persistent().has(&proof_id) {
+            return Err(MockVerifierError::ProofAlreadyUsed);
+        }
+
+        // Blob [count][public inputs][proof], comparado como en los verificadores
+        let count = if proof_blob.len() >= 4 {
+            let mut header = [0u8; 4];
+            proof_blob.slice(0..4).copy_into_slice(&mut header);
+            u32::from_be_bytes(header)
+        } else {
+            0
+        };
+        if count != expected.len() || proof_blob.len() < 4 + count * 32 {
+            return Err(MockVerifierError::PublicInputMismatch);
+        }
+        let mut public_inputs = Vec::new(&env);
+        for i in 0..count {
+            let mut field = [0u8; 32];
+            proof_blob
+                .slice(4 + i * 32..4 + (i + 1) * 32)
+                .copy_into_slice(&mut field);
+            let input = BytesN::from_array(&env, &field);
+            if !wildcards.contains(i) && input != expected.get_unchecked(i) {
+                return Err(MockVerifierError::PublicInputMismatch);
+            }
+            public_inputs.push_back(input);
+        }
+
+        env.storage().persistent().set(&proof_id, &true);
+        Ok(VerificationResult {
+            proof_id,
+            public_inputs,
+        })
+    }
+}
+
+fn setup(env: &Env) -> (StudentRegistryClient<'_>, Address) {
+    env.mock_all_auths();
+    let contract_id = env.register(StudentRegistry, ());
+    let client = StudentRegistryClient::new(env, &contract_id);
+    let admin = Address::generate(env);
+    client.initialize(&admin);
+    client.set_verifier(&admin, &env.register(MockVerifier, ()));
+    client.set_policy(&admin, &18, &1, &VALIDITY);
+    (client, admin)
+}
+
+/// Proof blob [count][public inputs][proof] del circuito `user-verification`:
+/// [min_age, min_semester, user_hash, código]
+fn verification_blob(
+    env: &Env,
+    user: &Address,
+    min_age: u64,
+    min_semester: u64,
+    code: u64,
+) -> Bytes {
+    let mut blob = Bytes::from_array(env, &4u32.to_be_bytes());
+    for input in [min_age, min_semester] {
+        blob.extend_from_array(&[0u8; 24]);
+        blob.extend_from_array(&input.to_be_bytes());
+    }
+    blob.extend_from_array(&StudentRegistry::user_hash(env, user).to_array());
+    blob.extend_from_array(&[0u8; 24]);
+    blob.extend_from_array(&code.to_be_bytes());
+    blob.extend_from_array(&[7u8; 100]);
+    blob
+}
+
+#[test]
+fn initialize_sets_admin_once() {
+    let env = Env::default();
+    let (client, admin) = setup(&env);
+
+    assert_eq!(
+        client.try_initialize(&Address::generate(&env)),
+        Err(Ok(Error::AlreadyInitialized))
+    );
+
+    // Solo el admin configura; otra cuenta no toma el control
+    let intruder = Address::generate(&env);
+    assert!(client.try_set_policy(&intruder, &0, &0, &VALIDITY).is_err());
+    client.set_policy(&admin, &20, &2, &VALIDITY);
+    assert_eq!(client.get_policy().unwrap().min_age, 20);
+}
+
+#[test]
+fn admin_calls_fail_before_initialize() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let client = StudentRegistryClient::new(&env, &env.register(StudentRegistry, ()));
+
+    let caller = Address::generate(&env);
+    assert!(client.try_set_policy(&caller, &18, &1, &VALIDITY).is_err());
+    assert!(client.get_policy().is_none());
+}
+
+#[test]
+fn submit_verification_registers_student() {
+    let env = Env::default();
+    let (client, _) = setup(&env);
+    env.ledger().set_timestamp(1_000);
+
+    let user = Address::generate(&env);
+    assert!(!client.is_verified_student(&user));
+
+    let until = client.submit_verification(&user, &verification_blob(&env, &user, 18, 2, 1));
+    assert_eq!(until, 1_000 + VALIDITY);
+    assert!(client.is_verified_student(&user));
+
+    // La verificación vence al cumplirse la vigencia
+    env.ledger().set_timestamp(until);
+    assert!(!client.is_verified_student(&user));
+}
+
+#[test]
+fn submit_verification_checks_public_inputs() {
+    let env = Env::default();
+    let (client, _) = setup(&env);
+    let user = Address::generate(&env);
+
+    // Mínimos por debajo de la política
+    assert_eq!(
+        client.try_submit_verification(&user, &verification_blob(&env, &user, 17, 2, 1)),
+        Err(Ok(Error::PolicyNotMet))
+    );
+    // Estudiante inactivo
+    assert_eq!(
+        client.try_submit_verification(&user, &verification_blob(&env, &user, 18, 2, 0)),
+        Err(Ok(Error::InvalidPublicInputs))
+    );
+    // Layout anterior de un solo public input: el verificador lo rechaza
+    let mut legacy = Bytes::from_array(&env, &1u32.to_be_bytes());
+    legacy.extend_from_array(&[0u8; 31]);
+    legacy.push_back(1);
+    assert_eq!(
+        client.try_submit_verification(&user, &legacy),
+        Err(Ok(Error::ProofRejected))
+    );
+    assert!(!client.is_verified_student(&user));
+}
+
+#[test]
+fn verification_proof_cannot_be_replayed() {
+    let env = Env::default();
+    let (client, _) = setup(&env);
+
+    let user = Address::generate(&env);
+    let blob = verification_blob(&env, &user, 18, 2, 1);
+    client.submit_verification(&user, &blob);
+
+    // El blob lleva el hash de `user`: el verificador lo rechaza para otra cuenta
+    let attacker = Address::generate(&env);
+    assert_eq!(
+        client.try_submit_verification(&attacker, &blob),
+        Err(Ok(Error::ProofRejected))
+    );
+    assert!(!client.is_verified_student(&attacker));
+
+    // El mismo usuario tampoco puede reutilizarlo: el verificador ya lo consumió
+    assert_eq!(
+        client.try_submit_verification(&user, &blob),
+        Err(Ok(Error::ProofRejected))
+    );
+}
+
+#[test]
+fn revoke_removes_verification() {
+    let env = Env::default();
+    let (client, admin) = setup(&env);
+
+    let user = Address::generate(&env);
+    client.submit_verification(&user, &verification_blob(&env, &user, 18, 2, 1));
+    client.revoke(&admin, &user);
+
+    assert!(client.get_verification(&user).is_none());
+    assert!(!client.is_verified_student(&user));
+}
+
+#[test]
+fn allowed_contracts_pass_student_gating() {
+    let env = Env::default();
+    let (client, admin) = setup(&env);
+
+    let savings_goals = Address::generate(&env);
+    assert!(!client.is_verified_student(&savings_goals));
+
+    client.set_allowed_contract(&admin, &savings_goals, &true);
+    assert!(client.is_allowed_contract(&savings_goals));
+    assert!(client.is_verified_student(&savings_goals));
+
+    client.set_allowed_contract(&admin, &savings_goals, &false);
+    assert!(!client.is_allowed_contract(&savings_goals));
+    assert!(!client.is_verified_student(&savings_goals));
+
+    // Solo el admin mantiene la lista
+    let intruder = Address::generate(&env);
+    assert!(client
+        .try_set_allowed_contract(&intruder, &savings_goals, &true)
+        .is_err());
+    assert!(!client.is_verified_student(&savings_goals));
+}
@@ -0,0 +1,83 @@
+#!/usr/bin/env python3
+"""Generate test VKs in the vk_fields.json layouts and the binary VK emitted by bb.
+
+Points are multiples of the BN254 generator (point i = (i+1)·G), so they are
+on the curve and in the subgroup, but they do not belong to a real circuit.
+They exercise the parser for each layout.
+"""
+import json
+import os
+
+P = 21888242871839275222246405745257275088696311157297823662689037894645226208583
+G = (1, 2)
+LOG_N = 12
+NUM_PUBLIC_INPUTS = 18
+PUB_INPUTS_OFFSET = 1
+Q_NNF_INDEX = 11  # right after q_memory
+
+
+def add(a, b):
+    if a is None:
+        return b
+    if a == b:
+        lam = 3 * a[0] * a[0] * pow(2 * a[1], P - 2, P) % P
+    else:
+        lam = (b[1] - a[1]) * pow(b[0] - a[0], P - 2, P) % P
+    x = (lam * lam - a[0] - b[0]) % P
+    return (x, (lam * (a[0] - x) - a[1]) % P)
+
+
+def points(count):
+    out, acc = [], None
+    for _ in range(count):
+        acc = add(acc, G)
+        out.append(acc)
+    return out
+
+
+def field(value):
+    return "0x%064x" % value
+
+
+def limbs(pt):
+    lo = (1 << 136) - 1
+    return [field(pt[0] & lo), field(pt[0] >> 136), field(pt[1] & lo), field(pt[1] >> 136)]
+
+
+def vk_fields(header, pts):
+    fields = [field(h) for h in header]
+    for pt in pts:
+        fields += limbs(pt)
+    return fields
+
+
+def vk_binary(header, pts):
+    out = b"".join(h.to_bytes(32, "big") for h in header)
+    for pt in pts:
+        out += pt[0].to_bytes(32, "big") + pt[1].to_bytes(32, "big")
+    return out
+
+
+def main():
+    here = os.path.dirname(os.path.abspath(__file__))
+    all_points = points(28)
+    without_nnf = all_points[:Q_NNF_INDEX] + all_points[Q_NNF_INDEX + 1:]
+
+    # bb < 0.84: circuit_size, inputs, offset, has_pairing_points + 16 indices
+    pairing = [1 << LOG_N, NUM_PUBLIC_INPUTS, PUB_INPUTS_OFFSET, 1] + list(range(16))
+    layouts = {
+        "vk_fields_pairing_indices.json": vk_fields(pairing, without_nnf),
+        "vk_fields_with_nnf.json": vk_fields([LOG_N, NUM_PUBLIC_INPUTS, PUB_INPUTS_OFFSET], all_points),
+    }
+    for name, fields in layouts.items():
+        with open(os.path.join(here, name), "w") as f:
+            json.dump(fields, f, indent=2)
+            f.write("\n")
+
+    # bb write_vk: same key as vk_fields_with_nnf.json
+    with open(os.path.join(here, "vk_with_nnf.bin"), "wb") as f:
+        f.write(vk_binary([LOG_N, NUM_PUBLIC_INPUTS, PUB_INPUTS_OFFSET], all_points))
+
+
+if __name__ == "__main__":
+    main()
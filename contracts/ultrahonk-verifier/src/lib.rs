@@ -5,9 +5,9 @@ use alloc::{boxed::Box, string::String as StdString, vec::Vec as StdVec};
 use core::str;
 
 use soroban_sdk::{
-    contract, contracterror, contractimpl, symbol_short,
+    contract, contracterror, contractimpl, contracttype, symbol_short,
     crypto::bn254::{Fr as HostFr, G1Affine as HostG1Affine, G2Affine as HostG2Affine},
-    Bytes, BytesN, Env, Symbol, Vec as SorobanVec,
+    Address, Bytes, BytesN, Env, Symbol, Vec as SorobanVec,
 };
 
 use ark_bn254::{Fq, Fq2, G1Affine as ArkG1Affine, G2Affine as ArkG2Affine};
@@ -296,103 +296,113 @@ fn try_read_g1(vk_fields: &[StdString], i: usize) -> Option<(G1Point, usize)> {
     Some((pt, i + 4))
 }
 
-/// Lookahead to find the first index where 27 consecutive points decode on-curve.
-/// This avoids false sync on a single accidental on-curve tuple.
-fn find_first_g1_start(
-    vk_fields: &[StdString],
-    start_guess: usize,
-    max_probe: usize,
-) -> Option<usize> {
-    const TOTAL_POINTS: usize = 27;
-    const TOTAL_LIMBS: usize = TOTAL_POINTS * 4;
+/// `vk_fields.json` layouts emitted by different bb releases, told apart by
+/// their field count. Every G1 point is 4 limbs (x_lo, x_hi, y_lo, y_hi).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum VkLayout {
+    /// bb < 0.84: [circuit_size, num_public_inputs, pub_inputs_offset,
+    /// has_pairing_points, 16 pairing-point indices] + 27 points
+    PairingIndices,
+    /// bb v0.87.0: [log_n, num_public_inputs, pub_inputs_offset] + 27 points.
+    /// Some v0.87 builds write the circuit size instead of log N; a header
+    /// value above `MAX_LOG_CIRCUIT_SIZE` is read as the circuit size.
+    V0_87,
+    /// Newer bb: v0.87.0 header + 28 points, with q_nnf after q_memory
+    WithNnf,
+}
 
-    let end = core::cmp::min(vk_fields.len(), start_guess + max_probe);
-    'probe: for i in start_guess..end {
-        if i + TOTAL_LIMBS > vk_fields.len() {
-            break;
+impl VkLayout {
+    const ALL: [VkLayout; 3] = [VkLayout::PairingIndices, VkLayout::V0_87, VkLayout::WithNnf];
+
+    fn header_len(self) -> usize {
+        match self {
+            VkLayout::PairingIndices => 20,
+            VkLayout::V0_87 | VkLayout::WithNnf => 3,
         }
-        let mut idx = i;
-        for _ in 0..TOTAL_POINTS {
-            if let Some((_pt, next)) = try_read_g1(vk_fields, idx) {
-                idx = next;
-            } else {
-                continue 'probe;
+    }
+
+    /// (circuit_size, log_circuit_size) from header field 0
+    fn circuit_size(self, h0: u64) -> Result<(u64, u64), Error> {
+        let from_log_n = |log_n: u64| {
+            if log_n == 0 || log_n > MAX_LOG_CIRCUIT_SIZE {
+                return Err(Error::VkInvalidHeader);
+            }
+            Ok((1u64 << log_n, log_n))
+        };
+        let from_size = |size: u64| {
+            if size < 2 || (size & (size - 1)) != 0 {
+                return Err(Error::VkInvalidHeader);
             }
+            from_log_n(size.trailing_zeros() as u64)
+        };
+
+        match self {
+            VkLayout::PairingIndices => from_size(h0),
+            VkLayout::V0_87 if h0 > MAX_LOG_CIRCUIT_SIZE => from_size(h0),
+            VkLayout::V0_87 | VkLayout::WithNnf => from_log_n(h0),
         }
-        return Some(i);
     }
-    None
+
+    fn has_q_nnf(self) -> bool {
+        matches!(self, VkLayout::WithNnf)
+    }
+
+    fn field_count(self) -> usize {
+        let points = if self.has_q_nnf() { 28 } else { 27 };
+        self.header_len() + points * 4
+    }
+
+    fn detect(field_count: usize) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|layout| layout.field_count() == field_count)
+    }
 }
 
-/// Manual loader for `vk_fields.json` (bb v0.87.0 layout) without serde.
-/// Replicates the ordering used in the library's parser and enforces robust sync.
-fn load_vk_from_json_no_serde(json_data: &str) -> Result<VerificationKey, ()> {
-    let vk_fields = parse_json_array_of_strings(json_data)?;
-    if vk_fields.len() < 3 + 4 {
-        return Err(());
+/// Largest log N the BN254 SRS supports
+const MAX_LOG_CIRCUIT_SIZE: u64 = 28;
+
+/// Parse a header field that must fit in a u64
+fn parse_header_u64(s: &str) -> Result<u64, Error> {
+    header_u64_from_be32(&hex_str_to_be32(s).ok_or(Error::VkInvalidHeader)?)
+}
+
+/// 32-byte big-endian header field that must fit in a u64
+fn header_u64_from_be32(bytes: &[u8; 32]) -> Result<u64, Error> {
+    if bytes[..24].iter().any(|b| *b != 0) {
+        return Err(Error::VkInvalidHeader);
     }
+    let mut value = [0u8; 8];
+    value.copy_from_slice(&bytes[24..]);
+    Ok(u64::from_be_bytes(value))
+}
 
-    // Header: [0]=logN or circuit_size, [1]=num_public_inputs, [2]=pub_inputs_offset
-    #[inline(always)]
-    fn parse_u64_hex_lsb(s: &str) -> u64 {
-        let h = s.trim_start_matches("0x");
-        let n = core::cmp::min(16, h.len());
-        let slice = &h[h.len() - n..];
-        let mut out: u64 = 0;
-        for ch in slice.chars() {
-            let v = match ch {
-                '0'..='9' => ch as u64 - '0' as u64,
-                'a'..='f' => 10 + (ch as u64 - 'a' as u64),
-                'A'..='F' => 10 + (ch as u64 - 'A' as u64),
-                _ => 0,
-            };
-            out = (out << 4) | v;
-        }
-        out
-    }
-
-    let h0 = parse_u64_hex_lsb(&vk_fields[0]);
-    let public_inputs_size = if vk_fields.len() > 1 {
-        parse_u64_hex_lsb(&vk_fields[1])
-    } else {
-        0
-    };
-    let pub_inputs_offset = if vk_fields.len() > 2 {
-        parse_u64_hex_lsb(&vk_fields[2])
-    } else {
-        0
-    };
+/// Manual loader for `vk_fields.json` without serde.
+/// The layout is picked from the field count; see `VkLayout`.
+fn load_vk_from_json_no_serde(json_data: &str) -> Result<VerificationKey, Error> {
+    let vk_fields = parse_json_array_of_strings(json_data).map_err(|_| Error::VkNotJsonArray)?;
+    let layout = VkLayout::detect(vk_fields.len()).ok_or(Error::VkUnknownLayout)?;
 
-    // Interpret h0: power-of-two => circuit_size; else => logN
-    let (circuit_size, log_circuit_size) = if h0 != 0 && (h0 & (h0 - 1)) == 0 {
-        // h0 is circuit_size
-        let mut lg = 0u64;
-        let mut n = h0;
-        while n > 1 {
-            n >>= 1;
-            lg += 1;
-        }
-        (h0, lg)
-    } else {
-        // h0 is logN
-        let cs = 1u64.checked_shl(h0 as u32).ok_or(())?;
-        (cs, h0)
-    };
+    // Header: [0]=logN or circuit_size (per layout), [1]=num_public_inputs, [2]=pub_inputs_offset
+    let h0 = parse_header_u64(&vk_fields[0])?;
+    let public_inputs_size = parse_header_u64(&vk_fields[1])?;
+    let pub_inputs_offset = parse_header_u64(&vk_fields[2])?;
 
-    // Start of G1 limbs: find index where 27 consecutive points decode properly.
-    let mut idx = find_first_g1_start(&vk_fields, 3, 64).ok_or(())?;
+    let (circuit_size, log_circuit_size) = layout.circuit_size(h0)?;
+
+    let mut idx = layout.header_len();
 
     macro_rules! read_g1 {
         () => {{
-            let (pt, next) = try_read_g1(&vk_fields, idx).ok_or(())?;
+            let (pt, next) = try_read_g1(&vk_fields, idx).ok_or(Error::VkInvalidPoint)?;
             idx = next;
             pt
         }};
     }
 
-    // Follow bb v0.87.0 vk_fields order:
+    // bb vk_fields order:
     // qm, qc, ql, qr, qo, q4, q_lookup, q_arith, q_delta_range, q_elliptic, q_memory (qAux),
-    // q_poseidon2_external, q_poseidon2_internal,
+    // [q_nnf], q_poseidon2_external, q_poseidon2_internal,
     // s1..s4,
     // id1..id4,
     // t1..t4,
@@ -408,6 +418,14 @@ fn load_vk_from_json_no_serde(json_data: &str) -> Result<VerificationKey, ()> {
     let q_delta_range = read_g1!();
     let q_elliptic = read_g1!();
     let q_memory = read_g1!(); // qAux
+    let q_nnf = if layout.has_q_nnf() {
+        read_g1!()
+    } else {
+        G1Point {
+            x: Fq::from(0u64),
+            y: Fq::from(0u64),
+        }
+    };
     let q_poseidon2_external = read_g1!();
     let q_poseidon2_internal = read_g1!();
     let s1 = read_g1!();
@@ -424,6 +442,8 @@ fn load_vk_from_json_no_serde(json_data: &str) -> Result<VerificationKey, ()> {
     let t4 = read_g1!();
     let lagrange_first = read_g1!();
     let lagrange_last = read_g1!();
+    // The layout was picked by field count, so every field has been read
+    debug_assert_eq!(idx, vk_fields.len());
 
     Ok(VerificationKey {
         circuit_size,
@@ -441,10 +461,7 @@ fn load_vk_from_json_no_serde(json_data: &str) -> Result<VerificationKey, ()> {
         q_delta_range,
         q_elliptic,
         q_memory,
-        q_nnf: G1Point {
-            x: Fq::from(0u64),
-            y: Fq::from(0u64),
-        },
+        q_nnf,
         q_poseidon2_external,
         q_poseidon2_internal,
         s1,
@@ -464,6 +481,213 @@ fn load_vk_from_json_no_serde(json_data: &str) -> Result<VerificationKey, ()> {
     })
 }
 
+/// Compact on-chain VK encoding, written once by `set_vk` so verification
+/// skips the JSON/hex parsing and the on-curve lookahead probe:
+/// [1-byte version][4 × u64 BE: circuit_size, log_circuit_size, public_inputs_size,
+/// pub_inputs_offset][28 × G1 as x‖y, 32-byte big-endian each]
+const VK_BIN_VERSION: u8 = 1;
+const VK_BIN_HEADER_LEN: usize = 1 + 4 * 8;
+const VK_BIN_POINTS: usize = 28;
+const VK_BIN_LEN: usize = VK_BIN_HEADER_LEN + VK_BIN_POINTS * 64;
+
+fn g1_to_be_bytes(pt: &G1Point, out: &mut StdVec<u8>) {
+    out.extend_from_slice(&fq_to_be_bytes(&pt.x));
+    out.extend_from_slice(&fq_to_be_bytes(&pt.y));
+}
+
+/// Points were validated when the key was stored, so no curve checks here.
+fn g1_from_be_bytes(bytes: &[u8]) -> G1Point {
+    let mut x = [0u8; 32];
+    let mut y = [0u8; 32];
+    x.copy_from_slice(&bytes[..32]);
+    y.copy_from_slice(&bytes[32..64]);
+    G1Point {
+        x: fq_from_be_bytes(&x),
+        y: fq_from_be_bytes(&y),
+    }
+}
+
+fn encode_vk_binary(vk: &VerificationKey) -> StdVec<u8> {
+    let mut out: StdVec<u8> = StdVec::with_capacity(VK_BIN_LEN);
+    out.push(VK_BIN_VERSION);
+    for field in [
+        vk.circuit_size,
+        vk.log_circuit_size,
+        vk.public_inputs_size,
+        vk.pub_inputs_offset,
+    ] {
+        out.extend_from_slice(&field.to_be_bytes());
+    }
+
+    // Same order as decode_vk_binary
+    for pt in [
+        &vk.qm,
+        &vk.qc,
+        &vk.ql,
+        &vk.qr,
+        &vk.qo,
+        &vk.q4,
+        &vk.q_lookup,
+        &vk.q_arith,
+        &vk.q_delta_range,
+        &vk.q_elliptic,
+        &vk.q_memory,
+        &vk.q_nnf,
+        &vk.q_poseidon2_external,
+        &vk.q_poseidon2_internal,
+        &vk.s1,
+        &vk.s2,
+        &vk.s3,
+        &vk.s4,
+        &vk.id1,
+        &vk.id2,
+        &vk.id3,
+        &vk.id4,
+        &vk.t1,
+        &vk.t2,
+        &vk.t3,
+        &vk.t4,
+        &vk.lagrange_first,
+        &vk.lagrange_last,
+    ] {
+        g1_to_be_bytes(pt, &mut out);
+    }
+    out
+}
+
+fn decode_vk_binary(bytes: &[u8]) -> Result<VerificationKey, ()> {
+    if bytes.len() != VK_BIN_LEN || bytes[0] != VK_BIN_VERSION {
+        return Err(());
+    }
+
+    let header = |i: usize| {
+        let mut field = [0u8; 8];
+        field.copy_from_slice(&bytes[1 + i * 8..9 + i * 8]);
+        u64::from_be_bytes(field)
+    };
+
+    let mut points = bytes[VK_BIN_HEADER_LEN..].chunks_exact(64);
+    macro_rules! read_g1 {
+        () => {
+            g1_from_be_bytes(points.next().ok_or(())?)
+        };
+    }
+
+    let qm = read_g1!();
+    let qc = read_g1!();
+    let ql = read_g1!();
+    let qr = read_g1!();
+    let qo = read_g1!();
+    let q4 = read_g1!();
+    let q_lookup = read_g1!();
+    let q_arith = read_g1!();
+    let q_delta_range = read_g1!();
+    let q_elliptic = read_g1!();
+    let q_memory = read_g1!();
+    let q_nnf = read_g1!();
+    let q_poseidon2_external = read_g1!();
+    let q_poseidon2_internal = read_g1!();
+    let s1 = read_g1!();
+    let s2 = read_g1!();
+    let s3 = read_g1!();
+    let s4 = read_g1!();
+    let id1 = read_g1!();
+    let id2 = read_g1!();
+    let id3 = read_g1!();
+    let id4 = read_g1!();
+    let t1 = read_g1!();
+    let t2 = read_g1!();
+    let t3 = read_g1!();
+    let t4 = read_g1!();
+    let lagrange_first = read_g1!();
+    let lagrange_last = read_g1!();
+
+    Ok(VerificationKey {
+        circuit_size: header(0),
+        log_circuit_size: header(1),
+        public_inputs_size: header(2),
+        pub_inputs_offset: header(3),
+        qm,
+        qc,
+        ql,
+        qr,
+        qo,
+        q4,
+        q_lookup,
+        q_arith,
+        q_delta_range,
+        q_elliptic,
+        q_memory,
+        q_nnf,
+        q_poseidon2_external,
+        q_poseidon2_internal,
+        s1,
+        s2,
+        s3,
+        s4,
+        id1,
+        id2,
+        id3,
+        id4,
+        t1,
+        t2,
+        t3,
+        t4,
+        lagrange_first,
+        lagrange_last,
+    })
+}
+
+/// bb raw binary VK: the same fields as `vk_fields.json` (v0.87.0 header
+/// [log_n, num_public_inputs, pub_inputs_offset] as 32-byte big-endian
+/// fields) followed by 27 G1 points, or 28 with q_nnf, each as x‖y
+/// (32-byte big-endian) instead of 4 limbs.
+const BB_VK_HEADER_LEN: usize = 3 * 32;
+/// Index of q_nnf among the points (right after q_memory)
+const Q_NNF_INDEX: usize = 11;
+
+/// Decode a bb binary VK into the on-chain encoding, rejecting
+/// non-canonical, off-curve or out-of-subgroup points.
+fn load_vk_from_bb_binary(bytes: &[u8]) -> Result<VerificationKey, Error> {
+    let layout = if bytes.len() == BB_VK_HEADER_LEN + 28 * 64 {
+        VkLayout::WithNnf
+    } else if bytes.len() == BB_VK_HEADER_LEN + 27 * 64 {
+        VkLayout::V0_87
+    } else {
+        return Err(Error::VkUnknownLayout);
+    };
+
+    let header = |i: usize| {
+        let mut field = [0u8; 32];
+        field.copy_from_slice(&bytes[i * 32..(i + 1) * 32]);
+        header_u64_from_be32(&field)
+    };
+    let (circuit_size, log_circuit_size) = layout.circuit_size(header(0)?)?;
+
+    let mut canonical: StdVec<u8> = StdVec::with_capacity(VK_BIN_LEN);
+    canonical.push(VK_BIN_VERSION);
+    for field in [circuit_size, log_circuit_size, header(1)?, header(2)?] {
+        canonical.extend_from_slice(&field.to_be_bytes());
+    }
+    for (i, point) in bytes[BB_VK_HEADER_LEN..].chunks_exact(64).enumerate() {
+        if !layout.has_q_nnf() && i == Q_NNF_INDEX {
+            canonical.extend_from_slice(&[0u8; 64]);
+        }
+
+        let pt = g1_from_be_bytes(point);
+        if fq_to_be_bytes(&pt.x)[..] != point[..32] || fq_to_be_bytes(&pt.y)[..] != point[32..] {
+            return Err(Error::VkInvalidPoint);
+        }
+        let aff = ArkG1Affine::new_unchecked(pt.x, pt.y);
+        if !aff.is_on_curve() || !aff.is_in_correct_subgroup_assuming_on_curve() {
+            return Err(Error::VkInvalidPoint);
+        }
+        canonical.extend_from_slice(point);
+    }
+
+    decode_vk_binary(&canonical).map_err(|_| Error::VkParseError)
+}
+
 /// Contract
 #[contract]
 pub struct UltraHonkVerifierContract;
@@ -562,11 +786,72 @@ pub enum Error {
     ProofParseError = 2,
     VerificationFailed = 3,
     VkNotSet = 4,
+    CircuitNotFound = 5,
+    NotAuthorized = 6,
+    AlreadyInitialized = 7,
+    PublicInputCountMismatch = 8,
+    PublicInputMismatch = 9,
+    VkNotJsonArray = 10,
+    VkUnknownLayout = 11,
+    VkInvalidHeader = 12,
+    VkInvalidPoint = 13,
+    ProofAlreadyUsed = 14,
+    ConsumerNotAllowed = 15,
+}
+
+/// Persistent storage keys for per-proof records
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DataKey {
+    Verified(BytesN<32>),
+    Consumed(BytesN<32>),
+}
+
+/// Written once per proof_id on its first successful verification.
+/// `submitter` is only known when the proof comes through `consume_proof`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VerificationRecord {
+    pub verified_at: u64,
+    pub vk_hash: BytesN<32>,
+    pub submitter: Option<Address>,
+}
+
+/// Nullifier record written by `consume_proof`
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ProofConsumption {
+    pub caller: Address,
+    pub context: BytesN<32>,
+    pub consumed_at: u64,
+}
+
+/// Per-proof records are extended to ~30 days (5 s ledgers) whenever they
+/// are written or verified again with less than ~15 days left
+const RECORD_TTL_THRESHOLD: u32 = 259_200;
+const RECORD_TTL_EXTEND_TO: u32 = 518_400;
+
+/// Pairing-point limbs that newer bb versions count in the VK's
+/// public_inputs_size but carry inside the proof section.
+const PAIRING_POINTS_SIZE: u64 = 16;
+
+/// Successful verification: proof_id plus the proof's public inputs
+/// (32-byte big-endian fields, in circuit order).
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VerificationResult {
+    pub proof_id: BytesN<32>,
+    pub public_inputs: soroban_sdk::Vec<BytesN<32>>,
 }
 
 #[contractimpl]
 impl UltraHonkVerifierContract {
     fn key_vk() -> Symbol {
+        symbol_short!("vk_bin")
+    }
+
+    /// VK JSON stored under `vk` by versions before the binary encoding
+    fn key_legacy_vk() -> Symbol {
         symbol_short!("vk")
     }
 
@@ -574,6 +859,94 @@ impl UltraHonkVerifierContract {
         symbol_short!("vk_hash")
     }
 
+    fn key_circuit_vk(circuit_id: &Symbol) -> (Symbol, Symbol) {
+        (symbol_short!("vk_bin"), circuit_id.clone())
+    }
+
+    fn key_circuit_vk_hash(circuit_id: &Symbol) -> (Symbol, Symbol) {
+        (symbol_short!("vk_hash"), circuit_id.clone())
+    }
+
+    fn key_circuits() -> Symbol {
+        symbol_short!("circuits")
+    }
+
+    fn key_admin() -> Symbol {
+        symbol_short!("admin")
+    }
+
+    fn key_consumer(consumer: &Address) -> (Symbol, Address) {
+        (symbol_short!("consumer"), consumer.clone())
+    }
+
+    /// `caller` must be the admin stored by `initialize`
+    fn assert_admin(env: &Env, caller: &Address) -> Result<(), Error> {
+        caller.require_auth();
+        let admin: Option<Address> = env.storage().instance().get(&Self::key_admin());
+        if admin.as_ref() != Some(caller) {
+            return Err(Error::NotAuthorized);
+        }
+        Ok(())
+    }
+
+    /// Set the admin that manages VKs. Call once, right after deployment
+    pub fn initialize(env: Env, admin: Address) -> Result<(), Error> {
+        admin.require_auth();
+        if env.storage().instance().has(&Self::key_admin()) {
+            return Err(Error::AlreadyInitialized);
+        }
+        env.storage().instance().set(&Self::key_admin(), &admin);
+        Ok(())
+    }
+
+    /// Current admin, if initialized
+    pub fn get_admin(env: Env) -> Option<Address> {
+        env.storage().instance().get(&Self::key_admin())
+    }
+
+    /// Allow `consumer` (e.g. savings-goals) to call `consume_proof`. Admin only
+    pub fn add_consumer(env: Env, caller: Address, consumer: Address) -> Result<(), Error> {
+        Self::assert_admin(&env, &caller)?;
+        env.storage()
+            .instance()
+            .set(&Self::key_consumer(&consumer), &true);
+        Ok(())
+    }
+
+    /// Revoke `consumer`'s access to `consume_proof`. Admin only
+    pub fn remove_consumer(env: Env, caller: Address, consumer: Address) -> Result<(), Error> {
+        Self::assert_admin(&env, &caller)?;
+        env.storage()
+            .instance()
+            .remove(&Self::key_consumer(&consumer));
+        Ok(())
+    }
+
+    /// Whether `consumer` may call `consume_proof`
+    pub fn is_consumer(env: Env, consumer: Address) -> bool {
+        env.storage()
+            .instance()
+            .has(&Self::key_consumer(&consumer))
+    }
+
+    /// On-chain binary encoding of `vk` and its hash. The hash is taken over
+    /// this canonical encoding, so it does not depend on the input format
+    fn encode_vk(env: &Env, vk: &VerificationKey) -> (Bytes, BytesN<32>) {
+        let vk_bin = Bytes::from_slice(env, &encode_vk_binary(vk));
+        let hash_bn: BytesN<32> = env.crypto().keccak256(&vk_bin).into();
+        (vk_bin, hash_bn)
+    }
+
+    fn store_vk(env: &Env, vk: &VerificationKey) -> BytesN<32> {
+        let (vk_bin, hash_bn) = Self::encode_vk(env, vk);
+        env.storage().instance().set(&Self::key_vk(), &vk_bin);
+        env.storage().instance().set(&Self::key_vk_hash(), &hash_bn);
+        hash_bn
+    }
+    fn decode_stored_vk(vk_bin: &Bytes) -> Result<VerificationKey, Error> {
+        decode_vk_binary(&vk_bin.to_alloc_vec()).map_err(|_| Error::VkParseError)
+    }
+
     /// Split a packed [4-byte count][public_inputs][proof] buffer into
     /// (public_inputs as 32-byte big-endian slices, proof bytes).
     /// Accepts proof sections of either 440 or 456 field elements (BN254), to be
@@ -600,20 +973,53 @@ impl UltraHonkVerifierContract {
         }
         (StdVec::new(), rest.to_vec())
     }
-    /// Verify an UltraHonk proof; on success store proof_id (= soroban sha256(proof_blob))
-    pub fn verify_proof(env: Env, vk_json: Bytes, proof_blob: Bytes) -> Result<BytesN<32>, Error> {
-        hash::set_soroban_hash_backend(Box::new(SorobanKeccak::new(&env)));
-        ec::set_soroban_bn254_backend(Box::new(SorobanBn254::new(&env)));
-
-        let proof_hash: BytesN<32> = env.crypto().keccak256(&proof_blob).into();
-        let proof_vec: StdVec<u8> = proof_blob.to_alloc_vec();
-
+    /// Parse a `vk_fields.json` payload into a VerificationKey
+    fn parse_vk_json(vk_json: &Bytes) -> Result<VerificationKey, Error> {
         // vk_json → &str  (avoid temporary drop by binding first)
         let vk_vec: StdVec<u8> = vk_json.to_alloc_vec();
-        let vk_str = str::from_utf8(&vk_vec).map_err(|_| Error::VkParseError)?;
+        let vk_str = str::from_utf8(&vk_vec).map_err(|_| Error::VkNotJsonArray)?;
 
         // Build VK (manual JSON parser; no serde_json needed)
-        let vk = load_vk_from_json_no_serde(vk_str).map_err(|_| Error::VkParseError)?;
+        load_vk_from_json_no_serde(vk_str)
+    }
+
+    /// Verify an UltraHonk proof; on success record proof_id (= keccak256(proof_blob))
+    pub fn verify_proof(env: Env, vk_json: Bytes, proof_blob: Bytes) -> Result<BytesN<32>, Error> {
+        let vk = Self::parse_vk_json(&vk_json)?;
+        let (_, vk_hash) = Self::encode_vk(&env, &vk);
+        Self::verify_with_vk(&env, vk, vk_hash, &proof_blob, None).map(|result| result.proof_id)
+    }
+
+    /// Check the packed blob's input count against its header and the VK
+    fn check_public_input_count(vk: &VerificationKey, packed: &[u8]) -> Result<(), Error> {
+        if packed.len() < 4 {
+            return Err(Error::ProofParseError);
+        }
+        let declared = u32::from_be_bytes([packed[0], packed[1], packed[2], packed[3]]) as u64;
+        let (pub_inputs_bytes, _) = Self::split_inputs_and_proof_bytes(packed);
+        if pub_inputs_bytes.len() as u64 != declared {
+            return Err(Error::PublicInputCountMismatch);
+        }
+
+        let expected = vk.public_inputs_size;
+        if declared != expected && declared + PAIRING_POINTS_SIZE != expected {
+            return Err(Error::PublicInputCountMismatch);
+        }
+        Ok(())
+    }
+
+    fn verify_with_vk(
+        env: &Env,
+        vk: VerificationKey,
+        vk_hash: BytesN<32>,
+        proof_blob: &Bytes,
+        submitter: Option<Address>,
+    ) -> Result<VerificationResult, Error> {
+        hash::set_soroban_hash_backend(Box::new(SorobanKeccak::new(env)));
+        ec::set_soroban_bn254_backend(Box::new(SorobanBn254::new(env)));
+
+        let proof_hash: BytesN<32> = env.crypto().keccak256(proof_blob).into();
+        let proof_vec: StdVec<u8> = proof_blob.to_alloc_vec();
 
         // Verifier (moves vk)
         let verifier = UltraHonkVerifier::new_with_vk(vk);
@@ -626,32 +1032,352 @@ impl UltraHonkVerifierContract {
             .verify(&proof_bytes, &pub_inputs_bytes)
             .map_err(|_| Error::VerificationFailed)?;
 
-        // Persist success
-        env.storage().instance().set(&proof_hash, &true);
+        // Persist success, kept out of instance storage. The first verification
+        // wins, except that a later known submitter fills in an anonymous one
+        let key = DataKey::Verified(proof_hash.clone());
+        let existing: Option<VerificationRecord> = env.storage().persistent().get(&key);
+        match existing {
+            None => {
+                let record = VerificationRecord {
+                    verified_at: env.ledger().timestamp(),
+                    vk_hash,
+                    submitter,
+                };
+                env.storage().persistent().set(&key, &record);
+            }
+            Some(mut record) if record.submitter.is_none() && submitter.is_some() => {
+                record.submitter = submitter;
+                env.storage().persistent().set(&key, &record);
+            }
+            Some(_) => {}
+        }
+        Self::extend_record(env, &key);
+
+        let mut public_inputs = SorobanVec::new(env);
+        for input in pub_inputs_bytes.iter() {
+            let mut field = [0u8; 32];
+            field.copy_from_slice(input);
+            public_inputs.push_back(BytesN::from_array(env, &field));
+        }
 
-        Ok(proof_hash)
+        Ok(VerificationResult {
+            proof_id: proof_hash,
+            public_inputs,
+        })
     }
 
-    /// Set verification key JSON and cache its hash. Returns vk_hash
-    pub fn set_vk(env: Env, vk_json: Bytes) -> Result<BytesN<32>, Error> {
-        env.storage().instance().set(&Self::key_vk(), &vk_json);
-        let hash_bn: BytesN<32> = env.crypto().keccak256(&vk_json).into();
-        env.storage().instance().set(&Self::key_vk_hash(), &hash_bn);
-        Ok(hash_bn)
+    /// Parse and validate the verification key JSON once, store its binary
+    /// encoding and cache its hash. Admin only. Returns vk_hash
+    pub fn set_vk(env: Env, caller: Address, vk_json: Bytes) -> Result<BytesN<32>, Error> {
+        Self::assert_admin(&env, &caller)?;
+        let vk = Self::parse_vk_json(&vk_json)?;
+        Ok(Self::store_vk(&env, &vk))
     }
 
-    /// Verify using the on-chain stored VK
-    pub fn verify_proof_with_stored_vk(env: Env, proof_blob: Bytes) -> Result<BytesN<32>, Error> {
+    /// Same as `set_vk` for the raw binary VK written by `bb write_vk`.
+    /// Admin only. Yields the same vk_hash as the equivalent `vk_fields.json`
+    pub fn set_vk_binary(env: Env, caller: Address, vk: Bytes) -> Result<BytesN<32>, Error> {
+        Self::assert_admin(&env, &caller)?;
+        let vk = load_vk_from_bb_binary(&vk.to_alloc_vec())?;
+        Ok(Self::store_vk(&env, &vk))
+    }
+
+    /// Parse the VK JSON that versions before the binary encoding stored under
+    /// `vk` and store it as if it came through `set_vk`; the legacy key is
+    /// deleted. vk_hash changes: it is now taken over the binary encoding.
+    /// Admin only. Returns the new vk_hash
+    pub fn migrate_legacy_vk(env: Env, caller: Address) -> Result<BytesN<32>, Error> {
+        Self::assert_admin(&env, &caller)?;
         let vk_json: Bytes = env
+            .storage()
+            .instance()
+            .get(&Self::key_legacy_vk())
+            .ok_or(Error::VkNotSet)?;
+        let vk = Self::parse_vk_json(&vk_json)?;
+        env.storage().instance().remove(&Self::key_legacy_vk());
+        Ok(Self::store_vk(&env, &vk))
+    }
+
+    /// Stored VK and its vk_hash
+    fn load_stored_vk(env: &Env) -> Result<(VerificationKey, BytesN<32>), Error> {
+        let vk_bin: Bytes = env
             .storage()
             .instance()
             .get(&Self::key_vk())
             .ok_or(Error::VkNotSet)?;
-        Self::verify_proof(env, vk_json, proof_blob)
+        let vk_hash: BytesN<32> = env
+            .storage()
+            .instance()
+            .get(&Self::key_vk_hash())
+            .ok_or(Error::VkNotSet)?;
+        Ok((Self::decode_stored_vk(&vk_bin)?, vk_hash))
+    }
+
+    /// VK registered for `circuit_id` and its vk_hash
+    fn load_circuit_vk(
+        env: &Env,
+        circuit_id: &Symbol,
+    ) -> Result<(VerificationKey, BytesN<32>), Error> {
+        let vk_bin: Bytes = env
+            .storage()
+            .persistent()
+            .get(&Self::key_circuit_vk(circuit_id))
+            .ok_or(Error::CircuitNotFound)?;
+        let vk_hash: BytesN<32> = env
+            .storage()
+            .persistent()
+            .get(&Self::key_circuit_vk_hash(circuit_id))
+            .ok_or(Error::CircuitNotFound)?;
+        Ok((Self::decode_stored_vk(&vk_bin)?, vk_hash))
+    }
+
+    /// VK registered for `circuit_id`, or the one set with `set_vk` if `None`
+    fn load_vk(
+        env: &Env,
+        circuit_id: &Option<Symbol>,
+    ) -> Result<(VerificationKey, BytesN<32>), Error> {
+        match circuit_id {
+            Some(circuit_id) => Self::load_circuit_vk(env, circuit_id),
+            None => Self::load_stored_vk(env),
+        }
+    }
+
+    /// Verify using the on-chain stored VK (binary, no re-parsing)
+    pub fn verify_proof_with_stored_vk(env: Env, proof_blob: Bytes) -> Result<BytesN<32>, Error> {
+        let (vk, vk_hash) = Self::load_stored_vk(&env)?;
+        Self::verify_with_vk(&env, vk, vk_hash, &proof_blob, None).map(|result| result.proof_id)
+    }
+
+    /// Verify against the VK registered for `circuit_id` (the `set_vk` one
+    /// if `None`) and return the proof_id together with the decoded public
+    /// inputs. Fails if the input count does not match the VK
+    pub fn verify_and_get_public_inputs(
+        env: Env,
+        circuit_id: Option<Symbol>,
+        proof_blob: Bytes,
+    ) -> Result<VerificationResult, Error> {
+        let (vk, vk_hash) = Self::load_vk(&env, &circuit_id)?;
+        Self::check_public_input_count(&vk, &proof_blob.to_alloc_vec())?;
+        Self::verify_with_vk(&env, vk, vk_hash, &proof_blob, None)
+    }
+
+    /// Verify against the VK of `circuit_id` (as in
+    /// `verify_and_get_public_inputs`) and bind the proof to on-chain state:
+    /// the embedded public inputs must equal `expected` position by position.
+    /// Positions listed in `wildcards` (e.g. circuit outputs) are not compared.
+    /// Returns proof_id
+    pub fn verify_with_expected_inputs(
+        env: Env,
+        circuit_id: Option<Symbol>,
+        proof_blob: Bytes,
+        expected: soroban_sdk::Vec<BytesN<32>>,
+        wildcards: soroban_sdk::Vec<u32>,
+    ) -> Result<BytesN<32>, Error> {
+        let (vk, vk_hash) = Self::load_vk(&env, &circuit_id)?;
+        Self::check_expected_inputs(&vk, &proof_blob, &expected, &wildcards)?;
+        Self::verify_with_vk(&env, vk, vk_hash, &proof_blob, None).map(|result| result.proof_id)
+    }
+
+    /// Input count check plus the `expected`/`wildcards` comparison, done
+    /// before the (expensive) cryptographic verification
+    fn check_expected_inputs(
+        vk: &VerificationKey,
+        proof_blob: &Bytes,
+        expected: &soroban_sdk::Vec<BytesN<32>>,
+        wildcards: &soroban_sdk::Vec<u32>,
+    ) -> Result<(), Error> {
+        let packed = proof_blob.to_alloc_vec();
+        Self::check_public_input_count(vk, &packed)?;
+
+        let (pub_inputs_bytes, _) = Self::split_inputs_and_proof_bytes(&packed);
+        if pub_inputs_bytes.len() != expected.len() as usize {
+            return Err(Error::PublicInputMismatch);
+        }
+        for (i, actual) in pub_inputs_bytes.iter().enumerate() {
+            let i = i as u32;
+            if wildcards.contains(i) {
+                continue;
+            }
+            let wanted = expected.get(i).ok_or(Error::PublicInputMismatch)?;
+            if actual.as_slice() != wanted.to_array().as_slice() {
+                return Err(Error::PublicInputMismatch);
+            }
+        }
+        Ok(())
+    }
+
+    /// Verify against the VK of `circuit_id` (the `set_vk` one if `None`)
+    /// and spend the proof: records
+    /// (proof_id, caller, context) and fails with `ProofAlreadyUsed` if the
+    /// proof was consumed before, by any caller or for any context.
+    /// `caller` is the consuming contract and must be allowed with
+    /// `add_consumer`; `context` is e.g. a goal or course id.
+    /// Public inputs are matched against `expected` as in
+    /// `verify_with_expected_inputs`. Returns proof_id and the public inputs
+    pub fn consume_proof(
+        env: Env,
+        caller: Address,
+        circuit_id: Option<Symbol>,
+        proof_blob: Bytes,
+        context: BytesN<32>,
+        expected: soroban_sdk::Vec<BytesN<32>>,
+        wildcards: soroban_sdk::Vec<u32>,
+    ) -> Result<VerificationResult, Error> {
+        caller.require_auth();
+        if !Self::is_consumer(env.clone(), caller.clone()) {
+            return Err(Error::ConsumerNotAllowed);
+        }
+
+        let proof_id: BytesN<32> = env.crypto().keccak256(&proof_blob).into();
+        if env.storage().persistent().has(&DataKey::Consumed(proof_id.clone())) {
+            return Err(Error::ProofAlreadyUsed);
+        }
+
+        let (vk, vk_hash) = Self::load_vk(&env, &circuit_id)?;
+        Self::check_expected_inputs(&vk, &proof_blob, &expected, &wildcards)?;
+        let result = Self::verify_with_vk(&env, vk, vk_hash, &proof_blob, Some(caller.clone()))?;
+
+        let record = ProofConsumption {
+            caller: caller.clone(),
+            context: context.clone(),
+            consumed_at: env.ledger().timestamp(),
+        };
+        let key = DataKey::Consumed(proof_id.clone());
+        env.storage().persistent().set(&key, &record);
+        Self::extend_record(&env, &key);
+
+        env.events()
+            .publish((symbol_short!("consumed"), caller, context), proof_id);
+
+        Ok(result)
     }
 
-    /// Query if a proof_id was previously verified
+    /// Who consumed `proof_id`, and for which context
+    pub fn get_proof_consumption(env: Env, proof_id: BytesN<32>) -> Option<ProofConsumption> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Consumed(proof_id.clone()))
+    }
+
+    /// Register (or replace) the VK for a circuit, e.g. `savings_proof`,
+    /// `course_completion`, `achievements`, `user_verification`. Admin only.
+    /// Returns the circuit's vk_hash
+    pub fn register_vk(
+        env: Env,
+        caller: Address,
+        circuit_id: Symbol,
+        vk_json: Bytes,
+    ) -> Result<BytesN<32>, Error> {
+        Self::assert_admin(&env, &caller)?;
+
+        let vk = Self::parse_vk_json(&vk_json)?;
+        let (vk_bin, hash_bn) = Self::encode_vk(&env, &vk);
+
+        // VKs are ~1.8KB each; keep them out of instance storage
+        env.storage()
+            .persistent()
+            .set(&Self::key_circuit_vk(&circuit_id), &vk_bin);
+        env.storage()
+            .persistent()
+            .set(&Self::key_circuit_vk_hash(&circuit_id), &hash_bn);
+
+        let mut circuits = Self::list_circuits(env.clone());
+        if !circuits.contains(&circuit_id) {
+            circuits.push_back(circuit_id.clone());
+            env.storage().instance().set(&Self::key_circuits(), &circuits);
+        }
+
+        env.events()
+            .publish((symbol_short!("vk_reg"), circuit_id), hash_bn.clone());
+
+        Ok(hash_bn)
+    }
+
+    /// Verify a proof against the VK registered for `circuit_id`
+    pub fn verify_for_circuit(
+        env: Env,
+        circuit_id: Symbol,
+        proof_blob: Bytes,
+    ) -> Result<BytesN<32>, Error> {
+        let (vk, vk_hash) = Self::load_circuit_vk(&env, &circuit_id)?;
+        Self::verify_with_vk(&env, vk, vk_hash, &proof_blob, None).map(|result| result.proof_id)
+    }
+
+    /// Circuit ids with a registered VK, in registration order
+    pub fn list_circuits(env: Env) -> soroban_sdk::Vec<Symbol> {
+        env.storage()
+            .instance()
+            .get(&Self::key_circuits())
+            .unwrap_or_else(|| SorobanVec::new(&env))
+    }
+
+    /// vk_hash of the VK registered for `circuit_id`
+    pub fn get_circuit_vk_hash(env: Env, circuit_id: Symbol) -> Option<BytesN<32>> {
+        env.storage()
+            .persistent()
+            .get(&Self::key_circuit_vk_hash(&circuit_id))
+    }
+
+    /// Verification record for `proof_id`, if it was verified
+    pub fn get_verification(env: Env, proof_id: BytesN<32>) -> Option<VerificationRecord> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Verified(proof_id))
+    }
+
+    /// Query if a proof_id was previously verified. Falls back to the legacy
+    /// instance-storage flag for proofs verified before records moved out
     pub fn is_verified(env: Env, proof_id: BytesN<32>) -> bool {
-        env.storage().instance().get(&proof_id).unwrap_or(false)
+        env.storage()
+            .persistent()
+            .has(&DataKey::Verified(proof_id.clone()))
+            || env.storage().instance().get(&proof_id).unwrap_or(false)
+    }
+
+    /// Move legacy instance flags (`proof_id → true`, written before records
+    /// moved to persistent storage) into `DataKey::Verified` records and
+    /// delete them. Instance keys cannot be listed, so the admin passes the
+    /// proof_ids (e.g. from past events). The flags kept no timestamp or VK:
+    /// records get the migration time and the stored vk_hash. Admin only.
+    /// Returns how many flags were migrated
+    pub fn migrate_legacy_proofs(
+        env: Env,
+        caller: Address,
+        proof_ids: soroban_sdk::Vec<BytesN<32>>,
+    ) -> Result<u32, Error> {
+        Self::assert_admin(&env, &caller)?;
+        let vk_hash: BytesN<32> = env
+            .storage()
+            .instance()
+            .get(&Self::key_vk_hash())
+            .unwrap_or_else(|| BytesN::from_array(&env, &[0u8; 32]));
+
+        let mut migrated = 0u32;
+        for proof_id in proof_ids.iter() {
+            if !env.storage().instance().get(&proof_id).unwrap_or(false) {
+                continue;
+            }
+            let key = DataKey::Verified(proof_id.clone());
+            if !env.storage().persistent().has(&key) {
+                let record = VerificationRecord {
+                    verified_at: env.ledger().timestamp(),
+                    vk_hash: vk_hash.clone(),
+                    submitter: None,
+                };
+                env.storage().persistent().set(&key, &record);
+            }
+            Self::extend_record(&env, &key);
+            env.storage().instance().remove(&proof_id);
+            migrated += 1;
+        }
+        Ok(migrated)
+    }
+
+    fn extend_record(env: &Env, key: &DataKey) {
+        env.storage()
+            .persistent()
+            .extend_ttl(key, RECORD_TTL_THRESHOLD, RECORD_TTL_EXTEND_TO);
     }
 }
+
+mod test;
@@ -0,0 +1,731 @@
+#![cfg(test)]
+extern crate std;
+
+use super::*;
+use soroban_sdk::testutils::{storage::Persistent as _, Address as _};
+use std::{format, string::ToString};
+
+const VK_PAIRING_INDICES: &str = include_str!("../fixtures/vk_fields_pairing_indices.json");
+const VK_WITH_NNF: &str = include_str!("../fixtures/vk_fields_with_nnf.json");
+const VK_WITH_NNF_BIN: &[u8] = include_bytes!("../fixtures/vk_with_nnf.bin");
+
+fn setup(env: &Env) -> (UltraHonkVerifierContractClient<'_>, Address) {
+    env.mock_all_auths();
+    let client =
+        UltraHonkVerifierContractClient::new(env, &env.register(UltraHonkVerifierContract, ()));
+    let admin = Address::generate(env);
+    client.initialize(&admin);
+    (client, admin)
+}
+
+#[test]
+fn vk_management_is_admin_only() {
+    let env = Env::default();
+    let (client, admin) = setup(&env);
+    let outsider = Address::generate(&env);
+    let not_a_vk = Bytes::from_slice(&env, b"{}");
+
+    assert_eq!(
+        client.try_initialize(&outsider),
+        Err(Ok(Error::AlreadyInitialized))
+    );
+    assert_eq!(client.get_admin(), Some(admin.clone()));
+
+    assert_eq!(
+        client.try_set_vk(&outsider, &not_a_vk),
+        Err(Ok(Error::NotAuthorized))
+    );
+    assert_eq!(
+        client.try_set_vk_binary(&outsider, &not_a_vk),
+        Err(Ok(Error::NotAuthorized))
+    );
+    assert_eq!(
+        client.try_register_vk(&outsider, &symbol_short!("savings"), &not_a_vk),
+        Err(Ok(Error::NotAuthorized))
+    );
+
+    // The admin passes the check and reaches the parser
+    assert_eq!(
+        client.try_set_vk(&admin, &not_a_vk),
+        Err(Ok(Error::VkNotJsonArray))
+    );
+}
+
+#[test]
+fn vk_management_requires_initialize() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let client =
+        UltraHonkVerifierContractClient::new(&env, &env.register(UltraHonkVerifierContract, ()));
+    let caller = Address::generate(&env);
+
+    // Without initialize nobody is admin; the first caller no longer takes the role
+    assert_eq!(
+        client.try_set_vk(&caller, &Bytes::from_slice(&env, b"[]")),
+        Err(Ok(Error::NotAuthorized))
+    );
+    assert_eq!(client.get_admin(), None);
+}
+
+/// Host cost (cpu, mem) of `f` running as the contract
+fn measure<T>(env: &Env, contract: &Address, f: impl FnOnce() -> T) -> (u64, u64, T) {
+    env.cost_estimate().budget().reset_default();
+    let out = env.as_contract(contract, f);
+    let budget = env.cost_estimate().budget();
+    (
+        budget.cpu_instruction_cost(),
+        budget.memory_bytes_cost(),
+        out,
+    )
+}
+
+#[test]
+fn stored_binary_vk_loads_cheaper_than_json() {
+    let env = Env::default();
+    let (client, admin) = setup(&env);
+    let contract = client.address.clone();
+    let vk_json = Bytes::from_slice(&env, VK_WITH_NNF.as_bytes());
+    client.set_vk(&admin, &vk_json);
+    let json_key = symbol_short!("vk_json");
+    env.as_contract(&contract, || {
+        env.storage().instance().set(&json_key, &vk_json)
+    });
+
+    // Before: each verification read the JSON and parsed it.
+    // After: it reads and decodes the binary encoding.
+    // The contract runs natively here: only host costs are metered (storage,
+    // Bytes copies); hex parsing also runs in WASM on the network, so the real
+    // gap is larger.
+    let (json_cpu, json_mem, before) = measure(&env, &contract, || {
+        let vk_json: Bytes = env.storage().instance().get(&json_key).unwrap();
+        let vk = UltraHonkVerifierContract::parse_vk_json(&vk_json).unwrap();
+        encode_vk_binary(&vk)
+    });
+    let (bin_cpu, bin_mem, after) = measure(&env, &contract, || {
+        let vk_bin: Bytes = env
+            .storage()
+            .instance()
+            .get(&UltraHonkVerifierContract::key_vk())
+            .unwrap();
+        let vk = decode_vk_binary(&vk_bin.to_alloc_vec()).unwrap();
+        encode_vk_binary(&vk)
+    });
+
+    assert_eq!(before, after);
+    assert!(bin_cpu < json_cpu, "cpu {} >= {}", bin_cpu, json_cpu);
+    // Natively both paths meter about the same host memory
+    assert!(bin_mem <= json_mem, "mem {} > {}", bin_mem, json_mem);
+}
+
+fn fields(json: &str) -> StdVec<StdString> {
+    parse_json_array_of_strings(json).unwrap()
+}
+
+fn to_json(fields: &[StdString]) -> StdString {
+    let quoted: StdVec<StdString> = fields.iter().map(|f| format!("\"{}\"", f)).collect();
+    format!("[{}]", quoted.join(","))
+}
+
+/// On-chain encoding of a parsed vk_fields.json
+fn encoded(json: &str) -> StdVec<u8> {
+    encode_vk_binary(&load_vk_from_json_no_serde(json).unwrap())
+}
+
+/// V0_87 layout: 3-field header with `h0` + the 27 points of the PairingIndices fixture
+fn v0_87_json(h0: u64) -> StdString {
+    let pairing = fields(VK_PAIRING_INDICES);
+    let mut out = std::vec![format!("0x{:064x}", h0)];
+    out.extend_from_slice(&pairing[1..3]);
+    out.extend_from_slice(&pairing[20..]);
+    to_json(&out)
+}
+
+fn q_nnf_slot(encoded: &[u8]) -> &[u8] {
+    let start = VK_BIN_HEADER_LEN + Q_NNF_INDEX * 64;
+    &encoded[start..start + 64]
+}
+
+#[test]
+fn parses_pairing_indices_layout() {
+    assert_eq!(
+        VkLayout::detect(fields(VK_PAIRING_INDICES).len()),
+        Some(VkLayout::PairingIndices)
+    );
+    let vk = load_vk_from_json_no_serde(VK_PAIRING_INDICES).unwrap();
+    assert_eq!(vk.circuit_size, 4096);
+    assert_eq!(vk.log_circuit_size, 12);
+    assert_eq!(vk.public_inputs_size, 18);
+    assert_eq!(vk.pub_inputs_offset, 1);
+
+    // This layout has no q_nnf: its slot stays zero
+    assert!(q_nnf_slot(&encoded(VK_PAIRING_INDICES))
+        .iter()
+        .all(|b| *b == 0));
+}
+
+#[test]
+fn parses_with_nnf_layout() {
+    assert_eq!(
+        VkLayout::detect(fields(VK_WITH_NNF).len()),
+        Some(VkLayout::WithNnf)
+    );
+    let with_nnf = encoded(VK_WITH_NNF);
+    let without_nnf = encoded(VK_PAIRING_INDICES);
+    assert!(q_nnf_slot(&with_nnf).iter().any(|b| *b != 0));
+
+    // Same header and points as the PairingIndices fixture, except q_nnf
+    let start = VK_BIN_HEADER_LEN + Q_NNF_INDEX * 64;
+    assert_eq!(with_nnf[..start], without_nnf[..start]);
+    assert_eq!(with_nnf[start + 64..], without_nnf[start + 64..]);
+}
+
+#[test]
+fn v0_87_reads_log_n_or_falls_back_to_circuit_size() {
+    let from_log_n = v0_87_json(12);
+    let from_size = v0_87_json(4096);
+    assert_eq!(
+        VkLayout::detect(fields(&from_log_n).len()),
+        Some(VkLayout::V0_87)
+    );
+
+    assert_eq!(encoded(&from_log_n), encoded(VK_PAIRING_INDICES));
+    assert_eq!(encoded(&from_size), encoded(VK_PAIRING_INDICES));
+
+    // Neither a valid log N nor a power of two
+    assert_eq!(
+        load_vk_from_json_no_serde(&v0_87_json(4097)).err(),
+        Some(Error::VkInvalidHeader)
+    );
+    assert_eq!(
+        load_vk_from_json_no_serde(&v0_87_json(0)).err(),
+        Some(Error::VkInvalidHeader)
+    );
+}
+
+#[test]
+fn reports_a_dedicated_error_per_layout_problem() {
+    assert_eq!(
+        load_vk_from_json_no_serde("{}").err(),
+        Some(Error::VkNotJsonArray)
+    );
+
+    let mut short = fields(VK_WITH_NNF);
+    short.pop();
+    assert_eq!(
+        load_vk_from_json_no_serde(&to_json(&short)).err(),
+        Some(Error::VkUnknownLayout)
+    );
+
+    let mut header = fields(VK_WITH_NNF);
+    header[1] = format!("0x{}", "f".repeat(64));
+    assert_eq!(
+        load_vk_from_json_no_serde(&to_json(&header)).err(),
+        Some(Error::VkInvalidHeader)
+    );
+
+    let mut point = fields(VK_WITH_NNF);
+    point[3] = "0x05".to_string(); // qm.x_lo
+    assert_eq!(
+        load_vk_from_json_no_serde(&to_json(&point)).err(),
+        Some(Error::VkInvalidPoint)
+    );
+}
+
+#[test]
+fn set_vk_hash_depends_only_on_the_parsed_key() {
+    let env = Env::default();
+    let (client, admin) = setup(&env);
+
+    let pairing = client.set_vk(
+        &admin,
+        &Bytes::from_slice(&env, VK_PAIRING_INDICES.as_bytes()),
+    );
+    let v0_87 = client.set_vk(
+        &admin,
+        &Bytes::from_slice(&env, v0_87_json(4096).as_bytes()),
+    );
+    let with_nnf = client.set_vk(&admin, &Bytes::from_slice(&env, VK_WITH_NNF.as_bytes()));
+
+    assert_eq!(pairing, v0_87);
+    assert_ne!(pairing, with_nnf);
+}
+
+#[test]
+fn set_vk_binary_matches_set_vk_json() {
+    let env = Env::default();
+    let (client, admin) = setup(&env);
+
+    let from_json = client.set_vk(&admin, &Bytes::from_slice(&env, VK_WITH_NNF.as_bytes()));
+    let from_bin = client.set_vk_binary(&admin, &Bytes::from_slice(&env, VK_WITH_NNF_BIN));
+    assert_eq!(from_bin, from_json);
+
+    // Without q_nnf (27 points) the binary VK matches the layouts without it
+    let mut without_nnf = VK_WITH_NNF_BIN.to_vec();
+    let q_nnf = BB_VK_HEADER_LEN + Q_NNF_INDEX * 64;
+    without_nnf.drain(q_nnf..q_nnf + 64);
+    let pairing = client.set_vk(
+        &admin,
+        &Bytes::from_slice(&env, VK_PAIRING_INDICES.as_bytes()),
+    );
+    assert_eq!(
+        client.set_vk_binary(&admin, &Bytes::from_slice(&env, &without_nnf)),
+        pairing
+    );
+}
+
+#[test]
+fn set_vk_binary_rejects_malformed_keys() {
+    let env = Env::default();
+    let (client, admin) = setup(&env);
+    let set = |bytes: &[u8]| client.try_set_vk_binary(&admin, &Bytes::from_slice(&env, bytes));
+
+    // The 4 × u64 on-chain header is not what bb writes: with 28 points it has
+    // the size of the 27-point bb layout and fails on the first header field
+    let mut u64_header: StdVec<u8> = [4096u64, 12, 18, 1]
+        .iter()
+        .flat_map(|field| field.to_be_bytes())
+        .collect();
+    u64_header.extend_from_slice(&VK_WITH_NNF_BIN[BB_VK_HEADER_LEN..]);
+    assert_eq!(set(&u64_header), Err(Ok(Error::VkInvalidHeader)));
+    assert_eq!(set(&VK_WITH_NNF_BIN[1..]), Err(Ok(Error::VkUnknownLayout)));
+
+    let mut bin = VK_WITH_NNF_BIN.to_vec();
+    bin[31] = 0;
+    assert_eq!(set(&bin), Err(Ok(Error::VkInvalidHeader)));
+
+    let mut bin = VK_WITH_NNF_BIN.to_vec();
+    bin[BB_VK_HEADER_LEN + 63] ^= 1;
+    assert_eq!(set(&bin), Err(Ok(Error::VkInvalidPoint)));
+}
+
+fn vk(env: &Env, json: &str) -> Bytes {
+    Bytes::from_slice(env, json.as_bytes())
+}
+
+/// Packed [count][public inputs][proof] blob with a 440-field proof that
+/// does not verify
+fn blob(env: &Env, inputs: &[u64]) -> Bytes {
+    let mut blob = Bytes::from_array(env, &(inputs.len() as u32).to_be_bytes());
+    for input in inputs {
+        blob.extend_from_array(&[0u8; 24]);
+        blob.extend_from_array(&input.to_be_bytes());
+    }
+    blob.extend_from_slice(&[0xffu8; 440 * 32]);
+    blob
+}
+
+/// Public inputs as 32-byte big-endian fields, as packed by `blob`
+fn inputs(env: &Env, values: &[u64]) -> soroban_sdk::Vec<BytesN<32>> {
+    let mut out = SorobanVec::new(env);
+    for value in values {
+        let mut field = [0u8; 32];
+        field[24..].copy_from_slice(&value.to_be_bytes());
+        out.push_back(BytesN::from_array(env, &field));
+    }
+    out
+}
+
+fn wildcards(env: &Env, positions: &[u32]) -> soroban_sdk::Vec<u32> {
+    SorobanVec::from_slice(env, positions)
+}
+
+#[test]
+fn register_vk_keeps_one_vk_per_circuit() {
+    let env = Env::default();
+    let (client, admin) = setup(&env);
+    let savings = Symbol::new(&env, "savings_proof");
+    let course = Symbol::new(&env, "course_completion");
+    assert!(client.list_circuits().is_empty());
+
+    let pairing_hash = client.register_vk(&admin, &savings, &vk(&env, VK_PAIRING_INDICES));
+    let nnf_hash = client.register_vk(&admin, &course, &vk(&env, VK_WITH_NNF));
+    assert_ne!(pairing_hash, nnf_hash);
+    assert_eq!(
+        client.get_circuit_vk_hash(&savings),
+        Some(pairing_hash.clone())
+    );
+
+    // Same hash as the default VK set from the same JSON
+    assert_eq!(
+        client.set_vk(&admin, &vk(&env, VK_PAIRING_INDICES)),
+        pairing_hash
+    );
+
+    // Registering again replaces the VK without listing the circuit twice
+    assert_eq!(
+        client.register_vk(&admin, &savings, &vk(&env, VK_WITH_NNF)),
+        nnf_hash
+    );
+    assert_eq!(client.get_circuit_vk_hash(&savings), Some(nnf_hash));
+    assert_eq!(
+        client.list_circuits(),
+        soroban_sdk::vec![&env, savings, course.clone()]
+    );
+    assert_eq!(
+        client.get_circuit_vk_hash(&Symbol::new(&env, "achievements")),
+        None
+    );
+
+    assert_eq!(
+        client.try_register_vk(&admin, &course, &vk(&env, "[]")),
+        Err(Ok(Error::VkUnknownLayout))
+    );
+}
+
+#[test]
+fn circuit_id_selects_the_registered_vk() {
+    let env = Env::default();
+    let (client, admin) = setup(&env);
+    let course = Symbol::new(&env, "course_completion");
+    let unknown = Some(Symbol::new(&env, "achievements"));
+    let consumer = Address::generate(&env);
+    client.add_consumer(&admin, &consumer);
+    let context = BytesN::from_array(&env, &[1u8; 32]);
+
+    assert_eq!(
+        client.try_verify_for_circuit(&course, &blob(&env, &[1, 2])),
+        Err(Ok(Error::CircuitNotFound))
+    );
+    client.register_vk(&admin, &course, &vk(&env, VK_WITH_NNF));
+
+    // The VK (18 inputs, 16 of them pairing points) is only reached by id
+    assert_eq!(
+        client.try_verify_and_get_public_inputs(&Some(course.clone()), &blob(&env, &[1, 2, 3])),
+        Err(Ok(Error::PublicInputCountMismatch))
+    );
+    assert_eq!(
+        client.try_verify_and_get_public_inputs(&None, &blob(&env, &[1, 2])),
+        Err(Ok(Error::VkNotSet))
+    );
+    assert_eq!(
+        client.try_verify_and_get_public_inputs(&unknown, &blob(&env, &[1, 2])),
+        Err(Ok(Error::CircuitNotFound))
+    );
+    assert_eq!(
+        client.try_verify_with_expected_inputs(
+            &unknown,
+            &blob(&env, &[1, 2]),
+            &inputs(&env, &[1, 2]),
+            &wildcards(&env, &[])
+        ),
+        Err(Ok(Error::CircuitNotFound))
+    );
+    assert_eq!(
+        client.try_consume_proof(
+            &consumer,
+            &unknown,
+            &blob(&env, &[1, 2]),
+            &context,
+            &inputs(&env, &[1, 2]),
+            &wildcards(&env, &[])
+        ),
+        Err(Ok(Error::CircuitNotFound))
+    );
+
+    // With the VK found, the proof itself is checked
+    assert_eq!(
+        client.try_verify_for_circuit(&course, &blob(&env, &[1, 2])),
+        Err(Ok(Error::VerificationFailed))
+    );
+    assert_eq!(
+        client.try_consume_proof(
+            &consumer,
+            &Some(course),
+            &blob(&env, &[1, 2]),
+            &context,
+            &inputs(&env, &[1, 2]),
+            &wildcards(&env, &[])
+        ),
+        Err(Ok(Error::VerificationFailed))
+    );
+}
+
+#[test]
+fn public_input_count_is_checked_before_verifying() {
+    let env = Env::default();
+    let (client, admin) = setup(&env);
+    client.set_vk(&admin, &vk(&env, VK_WITH_NNF));
+    let get_inputs = |blob: &Bytes| client.try_verify_and_get_public_inputs(&None, blob);
+
+    // The header declares 3 inputs but the blob carries 2
+    let mut lying = blob(&env, &[1, 2]);
+    lying.set(3, 3);
+    assert_eq!(get_inputs(&lying), Err(Ok(Error::PublicInputCountMismatch)));
+    // The VK counts 18 inputs, 16 of them pairing points carried in the proof
+    assert_eq!(
+        get_inputs(&blob(&env, &[1])),
+        Err(Ok(Error::PublicInputCountMismatch))
+    );
+    assert_eq!(
+        get_inputs(&blob(&env, &[1, 2])),
+        Err(Ok(Error::VerificationFailed))
+    );
+    assert_eq!(
+        get_inputs(&Bytes::from_slice(&env, &[0u8; 3])),
+        Err(Ok(Error::ProofParseError))
+    );
+}
+
+#[test]
+fn expected_inputs_are_compared_before_verifying() {
+    let env = Env::default();
+    let (client, admin) = setup(&env);
+    client.set_vk(&admin, &vk(&env, VK_WITH_NNF));
+    let verify = |values: &[u64], expected: &[u64], positions: &[u32]| {
+        client.try_verify_with_expected_inputs(
+            &None,
+            &blob(&env, values),
+            &inputs(&env, expected),
+            &wildcards(&env, positions),
+        )
+    };
+
+    assert_eq!(
+        verify(&[7, 1], &[7, 2], &[]),
+        Err(Ok(Error::PublicInputMismatch))
+    );
+    assert_eq!(
+        verify(&[7, 1], &[7], &[1]),
+        Err(Ok(Error::PublicInputMismatch))
+    );
+    // Wildcard positions are not compared: the proof itself is checked next
+    assert_eq!(
+        verify(&[7, 1], &[7, 2], &[1]),
+        Err(Ok(Error::VerificationFailed))
+    );
+    assert_eq!(
+        verify(&[7, 1], &[7, 1], &[]),
+        Err(Ok(Error::VerificationFailed))
+    );
+    // The count is checked against the VK first
+    assert_eq!(
+        verify(&[7], &[7], &[]),
+        Err(Ok(Error::PublicInputCountMismatch))
+    );
+}
+
+#[test]
+fn consume_proof_checks_expected_inputs() {
+    let env = Env::default();
+    let (client, admin) = setup(&env);
+    client.set_vk(&admin, &vk(&env, VK_WITH_NNF));
+    let consumer = Address::generate(&env);
+    client.add_consumer(&admin, &consumer);
+    let context = BytesN::from_array(&env, &[1u8; 32]);
+    let consume = |expected: &[u64], positions: &[u32]| {
+        client.try_consume_proof(
+            &consumer,
+            &None,
+            &blob(&env, &[7, 1]),
+            &context,
+            &inputs(&env, expected),
+            &wildcards(&env, positions),
+        )
+    };
+
+    assert_eq!(consume(&[8, 1], &[1]), Err(Ok(Error::PublicInputMismatch)));
+    assert_eq!(consume(&[7, 2], &[]), Err(Ok(Error::PublicInputMismatch)));
+    assert_eq!(consume(&[7, 2], &[1]), Err(Ok(Error::VerificationFailed)));
+}
+
+#[test]
+fn legacy_json_vk_migrates_to_binary() {
+    let env = Env::default();
+    let (client, admin) = setup(&env);
+    env.as_contract(&client.address, || {
+        env.storage()
+            .instance()
+            .set(&symbol_short!("vk"), &vk(&env, VK_WITH_NNF))
+    });
+
+    // Before the migration the binary VK is missing
+    assert_eq!(
+        client.try_verify_proof_with_stored_vk(&blob(&env, &[1, 2])),
+        Err(Ok(Error::VkNotSet))
+    );
+    assert_eq!(
+        client.try_migrate_legacy_vk(&Address::generate(&env)),
+        Err(Ok(Error::NotAuthorized))
+    );
+
+    let vk_hash = client.migrate_legacy_vk(&admin);
+    let expected: BytesN<32> = env
+        .crypto()
+        .keccak256(&Bytes::from_slice(&env, &encoded(VK_WITH_NNF)))
+        .into();
+    assert_eq!(vk_hash, expected);
+    assert_eq!(
+        client.try_verify_proof_with_stored_vk(&blob(&env, &[1, 2])),
+        Err(Ok(Error::VerificationFailed))
+    );
+    env.as_contract(&client.address, || {
+        assert!(!env.storage().instance().has(&symbol_short!("vk")))
+    });
+    assert_eq!(
+        client.try_migrate_legacy_vk(&admin),
+        Err(Ok(Error::VkNotSet))
+    );
+}
+
+#[test]
+fn consume_proof_is_limited_to_allowed_consumers() {
+    let env = Env::default();
+    let (client, admin) = setup(&env);
+    let consumer = Address::generate(&env);
+    let blob = Bytes::from_slice(&env, &[0u8; 4]);
+    let context = BytesN::from_array(&env, &[1u8; 32]);
+
+    assert_eq!(
+        client.try_consume_proof(
+            &consumer,
+            &None,
+            &blob,
+            &context,
+            &inputs(&env, &[]),
+            &wildcards(&env, &[])
+        ),
+        Err(Ok(Error::ConsumerNotAllowed))
+    );
+    assert_eq!(
+        client.try_add_consumer(&consumer, &consumer),
+        Err(Ok(Error::NotAuthorized))
+    );
+
+    // Allowed consumers get past the allowlist (here, to the missing VK)
+    client.add_consumer(&admin, &consumer);
+    assert!(client.is_consumer(&consumer));
+    assert_eq!(
+        client.try_consume_proof(
+            &consumer,
+            &None,
+            &blob,
+            &context,
+            &inputs(&env, &[]),
+            &wildcards(&env, &[])
+        ),
+        Err(Ok(Error::VkNotSet))
+    );
+
+    client.remove_consumer(&admin, &consumer);
+    assert!(!client.is_consumer(&consumer));
+    assert_eq!(
+        client.try_consume_proof(
+            &consumer,
+            &None,
+            &blob,
+            &context,
+            &inputs(&env, &[]),
+            &wildcards(&env, &[])
+        ),
+        Err(Ok(Error::ConsumerNotAllowed))
+    );
+}
+
+#[test]
+fn consume_proof_rejects_replays() {
+    let env = Env::default();
+    let (client, admin) = setup(&env);
+    client.set_vk(&admin, &vk(&env, VK_WITH_NNF));
+    let consumer = Address::generate(&env);
+    let other = Address::generate(&env);
+    client.add_consumer(&admin, &consumer);
+    client.add_consumer(&admin, &other);
+    let spent = blob(&env, &[7, 1]);
+    let proof_id: BytesN<32> = env.crypto().keccak256(&spent).into();
+    let context = BytesN::from_array(&env, &[1u8; 32]);
+
+    // No valid bb proof is available offline, so seed the nullifier that a
+    // successful consume_proof would have written
+    let record = ProofConsumption {
+        caller: consumer.clone(),
+        context: context.clone(),
+        consumed_at: env.ledger().timestamp(),
+    };
+    env.as_contract(&client.address, || {
+        env.storage()
+            .persistent()
+            .set(&DataKey::Consumed(proof_id.clone()), &record)
+    });
+    assert_eq!(client.get_proof_consumption(&proof_id), Some(record));
+
+    // Replays fail for the same caller, another caller or another context
+    let consume = |caller: &Address, context: &BytesN<32>| {
+        client.try_consume_proof(
+            caller,
+            &None,
+            &spent,
+            context,
+            &inputs(&env, &[7, 1]),
+            &wildcards(&env, &[]),
+        )
+    };
+    let other_context = BytesN::from_array(&env, &[2u8; 32]);
+    assert_eq!(
+        consume(&consumer, &context),
+        Err(Ok(Error::ProofAlreadyUsed))
+    );
+    assert_eq!(consume(&other, &context), Err(Ok(Error::ProofAlreadyUsed)));
+    assert_eq!(
+        consume(&consumer, &other_context),
+        Err(Ok(Error::ProofAlreadyUsed))
+    );
+
+    // Callers outside the allowlist are rejected before the nullifier check
+    assert_eq!(
+        consume(&Address::generate(&env), &context),
+        Err(Ok(Error::ConsumerNotAllowed))
+    );
+
+    // A fresh blob is not blocked by the nullifier and reaches verification
+    let fresh = blob(&env, &[7, 2]);
+    assert_eq!(
+        client.try_consume_proof(
+            &other,
+            &None,
+            &fresh,
+            &other_context,
+            &inputs(&env, &[7, 2]),
+            &wildcards(&env, &[]),
+        ),
+        Err(Ok(Error::VerificationFailed))
+    );
+    let fresh_id: BytesN<32> = env.crypto().keccak256(&fresh).into();
+    assert!(client.get_proof_consumption(&fresh_id).is_none());
+}
+
+#[test]
+fn legacy_proof_flags_migrate_to_persistent_records() {
+    let env = Env::default();
+    let (client, admin) = setup(&env);
+    let vk_hash = client.set_vk(&admin, &Bytes::from_slice(&env, VK_WITH_NNF.as_bytes()));
+    let legacy = BytesN::from_array(&env, &[1u8; 32]);
+    let unknown = BytesN::from_array(&env, &[2u8; 32]);
+    env.as_contract(&client.address, || {
+        env.storage().instance().set(&legacy, &true)
+    });
+    let ids = soroban_sdk::vec![&env, legacy.clone(), unknown.clone()];
+
+    assert_eq!(
+        client.try_migrate_legacy_proofs(&Address::generate(&env), &ids),
+        Err(Ok(Error::NotAuthorized))
+    );
+    assert_eq!(client.migrate_legacy_proofs(&admin, &ids), 1);
+
+    let record = client.get_verification(&legacy).unwrap();
+    assert_eq!(record.vk_hash, vk_hash);
+    assert_eq!(record.submitter, None);
+    assert!(client.is_verified(&legacy));
+    assert!(client.get_verification(&unknown).is_none());
+    env.as_contract(&client.address, || {
+        assert!(!env.storage().instance().has(&legacy));
+        assert_eq!(
+            env.storage()
+                .persistent()
+                .get_ttl(&DataKey::Verified(legacy.clone())),
+            RECORD_TTL_EXTEND_TO
+        );
+    });
+
+    // Already migrated: nothing left to move
+    assert_eq!(client.migrate_legacy_proofs(&admin, &ids), 0);
+}
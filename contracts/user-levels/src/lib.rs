@@ -2,13 +2,26 @@
 extern crate alloc;
 
 use soroban_sdk::{
-    contract, contracterror, contractimpl, panic_with_error, symbol_short, Address, Env, Symbol,
+    contract, contracterror, contractimpl, symbol_short, xdr::ToXdr, Address, Bytes, BytesN, Env,
+    IntoVal, String, Symbol, Val, Vec,
 };
 
+/// Máximo de usuarios recalculados por `update_levels_batch` en una transacción.
+/// Cada usuario toca ~7 entradas del ledger (nivel, badge, dueño del badge, logros)
+/// y escribe hasta 3; el límite de la red es de 100 entradas y 50 escrituras por
+/// transacción, por lo que el lote cabe hasta 14 usuarios. Se deja margen para
+/// cuando el conteo de metas y cursos consulte los otros contratos.
+/// `test::batch_at_cap_fits_budget` mide el lote completo.
+pub const MAX_BATCH_SIZE: u32 = 12;
+
+/// Circuito de los proofs de logros en el registro de VKs del verificador
+const ACHIEVEMENTS_CIRCUIT: &str = "achievements";
+
 /// Niveles de usuario basados en cumplimiento de metas y cursos
 /// Bronze: 1-2 metas o 1-2 cursos
 /// Silver: 3-5 metas o 3-5 cursos
 /// Gold: 6+ metas o 6+ cursos
+/// (un logro verificado Silver/Gold también otorga ese nivel como mínimo)
 /// Platinum: 10+ metas Y 10+ cursos
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[repr(u8)]
@@ -22,12 +35,82 @@ pub enum UserLevel {
 #[derive(Clone, Debug)]
 #[soroban_sdk::contracttype]
 pub struct UserLevelData {
-    pub level: u8,
+    pub level: u32,
     pub goals_achieved: u32,
     pub courses_completed: u32,
     pub last_updated: i64,
 }
 
+/// Beneficio (perk) otorgado por un socio del campus a partir de un nivel mínimo
+/// Ej: descuento Gold en cafeterías o en la librería
+#[derive(Clone, Debug)]
+#[soroban_sdk::contracttype]
+pub struct Perk {
+    pub partner: Address, // Socio registrado que otorga y canjea el perk
+    pub min_level: u32,
+    pub metadata: String, // Descripción / URI del beneficio
+    pub usage_limit: Option<u32>, // Usos máximos por periodo (None = ilimitado)
+    pub period_secs: u64, // Duración del periodo en segundos (0 = sin reinicio)
+    pub active: bool,
+}
+
+/// Uso de un perk por un usuario dentro del periodo vigente
+#[derive(Clone, Debug)]
+#[soroban_sdk::contracttype]
+pub struct PerkUsage {
+    pub period_start: u64,
+    pub count: u32,
+}
+
+/// Logro del catálogo (circuito `circuits/achievements`)
+/// Los mínimos públicos del proof deben ser >= a los definidos aquí
+#[derive(Clone, Debug)]
+#[soroban_sdk::contracttype]
+pub struct AchievementDef {
+    pub min_months: u64,
+    pub min_total_savings: u64,
+    pub min_transactions: u64,
+    pub min_compliance_rate: u64,
+    pub active: bool,
+}
+
+/// Tier desbloqueado por un usuario en un logro (1=Bronze, 2=Silver, 3=Gold)
+#[derive(Clone, Debug)]
+#[soroban_sdk::contracttype]
+pub struct AchievementRecord {
+    pub tier: u32,
+    pub proof_id: BytesN<32>,
+    pub unlocked_at: u64,
+}
+
+/// Resultado del recálculo de un usuario dentro de un lote
+/// `level` es None y `error` trae el código de `Error` si el usuario falló
+#[derive(Clone, Debug)]
+#[soroban_sdk::contracttype]
+pub struct LevelUpdateResult {
+    pub user: Address,
+    pub level: Option<u32>,
+    pub error: Option<u32>,
+}
+
+/// Resultado de `update_levels_batch`
+/// `processed` indica cuántos usuarios de la entrada se procesaron; los keepers
+/// continúan con `users[processed..]` en la siguiente transacción.
+#[derive(Clone, Debug)]
+#[soroban_sdk::contracttype]
+pub struct BatchUpdateResult {
+    pub processed: u32,
+    pub results: Vec<LevelUpdateResult>,
+}
+
+/// Resultado de `consume_proof` en el verificador: proof_id y public inputs
+#[derive(Clone, Debug)]
+#[soroban_sdk::contracttype]
+pub struct VerificationResult {
+    pub proof_id: BytesN<32>,
+    pub public_inputs: Vec<BytesN<32>>,
+}
+
 #[contracterror]
 #[repr(u32)]
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -36,6 +119,21 @@ pub enum Error {
     CourseCompletionNotConfigured = 2,
     NotAuthorized = 3,
     InvalidLevel = 4,
+    PerkNotFound = 5,
+    PerkInactive = 6,
+    LevelTooLow = 7,
+    PerkUsageLimitReached = 8,
+    AlreadyInitialized = 9,
+    PartnerNotRegistered = 10,
+    AchievementVerifierNotConfigured = 11,
+    AchievementNotFound = 12,
+    InvalidPublicInputs = 13,
+    AchievementRequirementsNotMet = 14,
+    InvalidTier = 15,
+    ProofRejected = 16,
+    VerifierCallFailed = 17,
+    BadgeNotFound = 18,
+    SoulboundToken = 19,
 }
 
 #[contract]
@@ -43,6 +141,17 @@ pub struct UserLevels;
 
 #[contractimpl]
 impl UserLevels {
+    /// Fija el admin del contrato. Se llama una sola vez, en la misma transacción
+    /// del despliegue.
+    pub fn initialize(env: Env, admin: Address) -> Result<(), Error> {
+        if env.storage().instance().has(&Self::admin_key()) {
+            return Err(Error::AlreadyInitialized);
+        }
+        admin.require_auth();
+        env.storage().instance().set(&Self::admin_key(), &admin);
+        Ok(())
+    }
+
     /// Configura las direcciones de los contratos relacionados
     pub fn set_contracts(
         env: Env,
@@ -59,7 +168,40 @@ impl UserLevels {
     }
 
     /// Calcula y actualiza el nivel de un usuario basado en metas y cursos
-    pub fn update_user_level(env: Env, user: Address) -> Result<u8, Error> {
+    pub fn update_user_level(env: Env, user: Address) -> Result<u32, Error> {
+        let (savings_goals, course_completion) = Self::get_contracts(&env)?;
+        Self::recompute_level(&env, &savings_goals, &course_completion, &user)
+    }
+
+    /// Recalcula el nivel de varios usuarios en una sola transacción.
+    /// Un error individual se reporta en su resultado sin abortar el lote.
+    /// Se procesan como máximo `MAX_BATCH_SIZE` usuarios.
+    pub fn update_levels_batch(env: Env, users: Vec<Address>) -> Result<BatchUpdateResult, Error> {
+        let (savings_goals, course_completion) = Self::get_contracts(&env)?;
+
+        let processed = core::cmp::min(users.len(), MAX_BATCH_SIZE);
+        let mut results = Vec::new(&env);
+
+        for user in users.iter().take(processed as usize) {
+            let result = match Self::recompute_level(&env, &savings_goals, &course_completion, &user) {
+                Ok(level) => LevelUpdateResult {
+                    user,
+                    level: Some(level),
+                    error: None,
+                },
+                Err(err) => LevelUpdateResult {
+                    user,
+                    level: None,
+                    error: Some(err as u32),
+                },
+            };
+            results.push_back(result);
+        }
+
+        Ok(BatchUpdateResult { processed, results })
+    }
+
+    fn get_contracts(env: &Env) -> Result<(Address, Address), Error> {
         let savings_goals: Address = env
             .storage()
             .instance()
@@ -72,27 +214,39 @@ impl UserLevels {
             .get(&Self::course_completion_key())
             .ok_or(Error::CourseCompletionNotConfigured)?;
 
+        Ok((savings_goals, course_completion))
+    }
+
+    fn recompute_level(
+        env: &Env,
+        savings_goals: &Address,
+        course_completion: &Address,
+        user: &Address,
+    ) -> Result<u32, Error> {
         // Contar metas alcanzadas (simplificado - en producción se iteraría)
-        let goals_achieved = Self::count_achieved_goals(&env, &savings_goals, &user)?;
+        let goals_achieved = Self::count_achieved_goals(env, savings_goals, user)?;
         
         // Contar cursos completados (simplificado - en producción se iteraría)
-        let courses_completed = Self::count_completed_courses(&env, &course_completion, &user)?;
+        let courses_completed = Self::count_completed_courses(env, course_completion, user)?;
 
-        // Calcular nivel
-        let level = Self::calculate_level(goals_achieved, courses_completed);
+        // Calcular nivel (incluye el mejor tier de logros verificados)
+        let achievement_tier = Self::get_achievement_tier(env.clone(), user.clone());
+        let level = Self::calculate_level(goals_achieved, courses_completed, achievement_tier);
 
         let level_data = UserLevelData {
-            level: level as u8,
+            level: level as u32,
             goals_achieved,
             courses_completed,
-            last_updated: env.ledger().timestamp(),
+            last_updated: env.ledger().timestamp() as i64,
         };
 
         env.storage()
             .persistent()
-            .set(&Self::user_level_key(&user), &level_data);
+            .set(&Self::user_level_key(user), &level_data);
 
-        Ok(level as u8)
+        Self::sync_badge(env, user, level as u32);
+
+        Ok(level as u32)
     }
 
     /// Obtiene el nivel actual de un usuario
@@ -100,26 +254,498 @@ impl UserLevels {
         env.storage().persistent().get(&Self::user_level_key(&user))
     }
 
-    /// Obtiene el nivel como u8 (1=Bronze, 2=Silver, 3=Gold, 4=Platinum)
-    pub fn get_user_level_value(env: Env, user: Address) -> u8 {
+    /// Obtiene el nivel como número (1=Bronze, 2=Silver, 3=Gold, 4=Platinum)
+    pub fn get_user_level_value(env: Env, user: Address) -> u32 {
         Self::get_user_level(env, user)
             .map(|data| data.level)
             .unwrap_or(1) // Bronze por defecto
     }
 
-    fn calculate_level(goals_achieved: u32, courses_completed: u32) -> UserLevel {
+    /// Nombre del token de nivel (badge soulbound)
+    pub fn name(env: Env) -> String {
+        String::from_str(&env, "PumaPay Level")
+    }
+
+    /// Símbolo del token de nivel
+    pub fn symbol(env: Env) -> String {
+        String::from_str(&env, "PUMALVL")
+    }
+
+    /// El badge no es divisible
+    pub fn decimals(_env: Env) -> u32 {
+        0
+    }
+
+    /// Cada dirección tiene como máximo un badge
+    pub fn balance(env: Env, id: Address) -> i128 {
+        if env.storage().persistent().has(&Self::badge_key(&id)) {
+            1
+        } else {
+            0
+        }
+    }
+
+    /// Obtiene el token_id del badge de un usuario
+    pub fn badge_of(env: Env, owner: Address) -> Option<u32> {
+        env.storage().persistent().get(&Self::badge_key(&owner))
+    }
+
+    /// Obtiene el dueño de un badge
+    pub fn owner_of(env: Env, token_id: u32) -> Option<Address> {
+        env.storage()
+            .persistent()
+            .get(&Self::badge_owner_key(token_id))
+    }
+
+    /// Metadata JSON del badge generada on-chain a partir del nivel actual
+    pub fn token_uri(env: Env, token_id: u32) -> Result<String, Error> {
+        let owner = Self::owner_of(env.clone(), token_id).ok_or(Error::BadgeNotFound)?;
+        let data = Self::get_user_level(env.clone(), owner).ok_or(Error::BadgeNotFound)?;
+
+        let name = Self::level_name(data.level);
+        let json = alloc::format!(
+            "{{\"name\":\"PumaPay {}\",\"description\":\"Nivel PumaPay (soulbound)\",\"attributes\":{{\"level\":\"{}\",\"level_value\":{},\"goals_achieved\":{},\"courses_completed\":{},\"last_updated\":{}}}}}",
+            name,
+            name,
+            data.level,
+            data.goals_achieved,
+            data.courses_completed,
+            data.last_updated,
+        );
+
+        Ok(String::from_str(&env, &json))
+    }
+
+    /// El badge es soulbound: no se puede transferir
+    pub fn transfer(_env: Env, _from: Address, _to: Address, _amount: i128) -> Result<(), Error> {
+        Err(Error::SoulboundToken)
+    }
+
+    /// Registra o reactiva un socio del campus que puede otorgar perks (solo admin)
+    pub fn register_partner(env: Env, partner: Address) {
+        Self::assert_admin(&env);
+        env.storage()
+            .persistent()
+            .set(&Self::partner_key(&partner), &true);
+        env.events().publish((symbol_short!("prtnr_reg"), partner), true);
+    }
+
+    /// Da de baja a un socio; sus perks dejan de poder canjearse (solo admin)
+    pub fn remove_partner(env: Env, partner: Address) {
+        Self::assert_admin(&env);
+        env.storage()
+            .persistent()
+            .remove(&Self::partner_key(&partner));
+        env.events().publish((symbol_short!("prtnr_reg"), partner), false);
+    }
+
+    /// Indica si una dirección es un socio registrado
+    pub fn is_partner(env: Env, partner: Address) -> bool {
+        env.storage()
+            .persistent()
+            .get(&Self::partner_key(&partner))
+            .unwrap_or(false)
+    }
+
+    /// Registra o actualiza un perk de un socio registrado (solo admin)
+    pub fn register_perk(
+        env: Env,
+        perk_id: u32,
+        partner: Address,
+        min_level: u32,
+        metadata: String,
+        usage_limit: Option<u32>,
+        period_secs: u64,
+    ) -> Result<(), Error> {
+        Self::assert_admin(&env);
+        if min_level < UserLevel::Bronze as u32 || min_level > UserLevel::Platinum as u32 {
+            return Err(Error::InvalidLevel);
+        }
+        if !Self::is_partner(env.clone(), partner.clone()) {
+            return Err(Error::PartnerNotRegistered);
+        }
+
+        let perk = Perk {
+            partner,
+            min_level,
+            metadata,
+            usage_limit,
+            period_secs,
+            active: true,
+        };
+
+        env.storage().persistent().set(&Self::perk_key(perk_id), &perk);
+        env.events()
+            .publish((symbol_short!("perk_reg"), perk_id), min_level);
+
+        Ok(())
+    }
+
+    /// Activa o desactiva un perk existente (solo admin)
+    pub fn set_perk_active(env: Env, perk_id: u32, active: bool) -> Result<(), Error> {
+        Self::assert_admin(&env);
+        let mut perk: Perk = env
+            .storage()
+            .persistent()
+            .get(&Self::perk_key(perk_id))
+            .ok_or(Error::PerkNotFound)?;
+
+        perk.active = active;
+        env.storage().persistent().set(&Self::perk_key(perk_id), &perk);
+
+        Ok(())
+    }
+
+    /// Obtiene la definición de un perk
+    pub fn get_perk(env: Env, perk_id: u32) -> Option<Perk> {
+        env.storage().persistent().get(&Self::perk_key(perk_id))
+    }
+
+    /// Canjea un perk para un usuario. Lo llaman los contratos socios
+    /// (cafeterías, librería) para no reimplementar la verificación de nivel.
+    /// Requiere la firma del socio dueño del perk y la del usuario.
+    /// Retorna el número de usos del usuario en el periodo actual.
+    pub fn redeem_perk(
+        env: Env,
+        partner: Address,
+        user: Address,
+        perk_id: u32,
+    ) -> Result<u32, Error> {
+        partner.require_auth();
+        user.require_auth();
+
+        let perk: Perk = env
+            .storage()
+            .persistent()
+            .get(&Self::perk_key(perk_id))
+            .ok_or(Error::PerkNotFound)?;
+
+        if perk.partner != partner {
+            return Err(Error::NotAuthorized);
+        }
+        if !Self::is_partner(env.clone(), partner) {
+            return Err(Error::PartnerNotRegistered);
+        }
+        if !perk.active {
+            return Err(Error::PerkInactive);
+        }
+
+        let level = Self::get_user_level_value(env.clone(), user.clone());
+        if level < perk.min_level {
+            return Err(Error::LevelTooLow);
+        }
+
+        // Reiniciar el conteo si cambió el periodo
+        let now = env.ledger().timestamp();
+        let period_start = if perk.period_secs > 0 {
+            now - (now % perk.period_secs)
+        } else {
+            0
+        };
+
+        let usage_key = Self::perk_usage_key(perk_id, &user);
+        let mut usage = env
+            .storage()
+            .persistent()
+            .get::<_, PerkUsage>(&usage_key)
+            .filter(|usage| usage.period_start == period_start)
+            .unwrap_or(PerkUsage {
+                period_start,
+                count: 0,
+            });
+
+        if let Some(limit) = perk.usage_limit {
+            if usage.count >= limit {
+                return Err(Error::PerkUsageLimitReached);
+            }
+        }
+
+        usage.count += 1;
+        env.storage().persistent().set(&usage_key, &usage);
+
+        env.events()
+            .publish((symbol_short!("perk_rdm"), user, perk_id), usage.count);
+
+        Ok(usage.count)
+    }
+
+    /// Obtiene los usos de un perk por un usuario en el periodo actual
+    pub fn get_perk_usage(env: Env, user: Address, perk_id: u32) -> u32 {
+        let perk: Option<Perk> = env.storage().persistent().get(&Self::perk_key(perk_id));
+        let period_start = match perk {
+            Some(perk) if perk.period_secs > 0 => {
+                let now = env.ledger().timestamp();
+                now - (now % perk.period_secs)
+            }
+            _ => 0,
+        };
+
+        env.storage()
+            .persistent()
+            .get::<_, PerkUsage>(&Self::perk_usage_key(perk_id, &user))
+            .filter(|usage| usage.period_start == period_start)
+            .map(|usage| usage.count)
+            .unwrap_or(0)
+    }
+
+    /// Configura el contrato verificador Ultrahonk para proofs de logros
+    pub fn set_achievement_verifier(env: Env, verifier: Address) {
+        Self::assert_admin(&env);
+        env.storage()
+            .instance()
+            .set(&Self::achievement_verifier_key(), &verifier);
+    }
+
+    /// Define o actualiza un logro del catálogo (solo admin)
+    pub fn define_achievement(
+        env: Env,
+        achievement_id: u32,
+        min_months: u64,
+        min_total_savings: u64,
+        min_transactions: u64,
+        min_compliance_rate: u64,
+    ) {
+        Self::assert_admin(&env);
+        let achievement = AchievementDef {
+            min_months,
+            min_total_savings,
+            min_transactions,
+            min_compliance_rate,
+            active: true,
+        };
+        env.storage()
+            .persistent()
+            .set(&Self::achievement_key(achievement_id), &achievement);
+    }
+
+    /// Obtiene la definición de un logro
+    pub fn get_achievement(env: Env, achievement_id: u32) -> Option<AchievementDef> {
+        env.storage()
+            .persistent()
+            .get(&Self::achievement_key(achievement_id))
+    }
+
+    /// Envía un proof del circuito de logros y registra el tier desbloqueado.
+    /// Public inputs esperados: [min_months, min_total_savings, min_transactions,
+    /// min_compliance_rate, user_hash, tier]. `user_hash` liga el proof a `user`
+    /// (ver `user_hash`), así que no puede reutilizarse para otra cuenta.
+    /// Retorna el tier registrado para el logro.
+    pub fn submit_achievement(
+        env: Env,
+        user: Address,
+        achievement_id: u32,
+        proof_blob: Bytes,
+    ) -> Result<u32, Error> {
+        user.require_auth();
+
+        let achievement: AchievementDef = env
+            .storage()
+            .persistent()
+            .get(&Self::achievement_key(achievement_id))
+            .ok_or(Error::AchievementNotFound)?;
+
+        if !achievement.active {
+            return Err(Error::AchievementNotFound);
+        }
+
+        let verifier: Address = env
+            .storage()
+            .instance()
+            .get(&Self::achievement_verifier_key())
+            .ok_or(Error::AchievementVerifierNotConfigured)?;
+
+        // El verificador exige el hash de `user` en la posición 4; los mínimos
+        // y el tier se leen de los public inputs verificados
+        let zero = BytesN::from_array(&env, &[0u8; 32]);
+        let mut expected = Vec::new(&env);
+        for _ in 0..4 {
+            expected.push_back(zero.clone());
+        }
+        expected.push_back(Self::user_hash(&env, &user));
+        expected.push_back(zero);
+        let wildcards = Vec::from_array(&env, [0, 1, 2, 3, 5]);
+        let result = Self::consume_proof(&env, &verifier, &user, proof_blob, expected, wildcards)?;
+
+        let inputs = &result.public_inputs;
+        if Self::input_u64(inputs, 0)? < achievement.min_months
+            || Self::input_u64(inputs, 1)? < achievement.min_total_savings
+            || Self::input_u64(inputs, 2)? < achievement.min_transactions
+            || Self::input_u64(inputs, 3)? < achievement.min_compliance_rate
+        {
+            return Err(Error::AchievementRequirementsNotMet);
+        }
+
+        let tier = Self::input_u64(inputs, 5)?;
+        if tier < UserLevel::Bronze as u64 || tier > UserLevel::Gold as u64 {
+            return Err(Error::InvalidTier);
+        }
+        let tier = tier as u32;
+        let proof_id = result.proof_id;
+
+        // Conservar el mejor tier por logro
+        let record_key = Self::achievement_record_key(&user, achievement_id);
+        if let Some(existing) = env
+            .storage()
+            .persistent()
+            .get::<_, AchievementRecord>(&record_key)
+        {
+            if existing.tier >= tier {
+                return Ok(existing.tier);
+            }
+        }
+
+        let record = AchievementRecord {
+            tier,
+            proof_id,
+            unlocked_at: env.ledger().timestamp(),
+        };
+        env.storage().persistent().set(&record_key, &record);
+
+        let best_key = Self::achievement_best_key(&user);
+        let best: u32 = env.storage().persistent().get(&best_key).unwrap_or(0);
+        if tier > best {
+            env.storage().persistent().set(&best_key, &tier);
+        }
+
+        env.events()
+            .publish((symbol_short!("ach_unlk"), user.clone(), achievement_id), tier);
+
+        // Reflejar el logro en el nivel si los contratos ya están configurados
+        if let Ok((savings_goals, course_completion)) = Self::get_contracts(&env) {
+            Self::recompute_level(&env, &savings_goals, &course_completion, &user)?;
+        }
+
+        Ok(tier)
+    }
+
+    /// Obtiene el tier desbloqueado por un usuario en un logro
+    pub fn get_user_achievement(
+        env: Env,
+        user: Address,
+        achievement_id: u32,
+    ) -> Option<AchievementRecord> {
+        env.storage()
+            .persistent()
+            .get(&Self::achievement_record_key(&user, achievement_id))
+    }
+
+    /// Obtiene el mejor tier de logros del usuario (0 = ninguno)
+    pub fn get_achievement_tier(env: Env, user: Address) -> u32 {
+        env.storage()
+            .persistent()
+            .get(&Self::achievement_best_key(&user))
+            .unwrap_or(0)
+    }
+
+    /// Verifica y consume el proof en el verificador, ligándolo a `user`. El
+    /// verificador compara los public inputs con `expected` salvo en las
+    /// posiciones de `wildcards` y retorna el proof_id y los public inputs.
+    /// Un proof inválido o ya usado se reporta como error en lugar de abortar.
+    fn consume_proof(
+        env: &Env,
+        verifier: &Address,
+        user: &Address,
+        proof_blob: Bytes,
+        expected: Vec<BytesN<32>>,
+        wildcards: Vec<u32>,
+    ) -> Result<VerificationResult, Error> {
+        let context: BytesN<32> = env.crypto().sha256(&user.clone().to_xdr(env)).into();
+
+        let mut args: Vec<Val> = Vec::new(env);
+        args.push_back(env.current_contract_address().into_val(env));
+        args.push_back(Some(Symbol::new(env, ACHIEVEMENTS_CIRCUIT)).into_val(env));
+        args.push_back(proof_blob.into_val(env));
+        args.push_back(context.into_val(env));
+        args.push_back(expected.into_val(env));
+        args.push_back(wildcards.into_val(env));
+
+        match env.try_invoke_contract::<VerificationResult, soroban_sdk::Error>(
+            verifier,
+            &Symbol::new(env, "consume_proof"),
+            args,
+        ) {
+            Ok(Ok(result)) => Ok(result),
+            Err(Ok(_)) => Err(Error::ProofRejected),
+            _ => Err(Error::VerifierCallFailed),
+        }
+    }
+
+    /// Hash de `user` que el circuito expone como public input: sha256 del XDR
+    /// de la dirección con el primer byte en cero, para que quepa en un campo BN254.
+    fn user_hash(env: &Env, user: &Address) -> BytesN<32> {
+        let mut hash = env.crypto().sha256(&user.clone().to_xdr(env)).to_array();
+        hash[0] = 0;
+        BytesN::from_array(env, &hash)
+    }
+
+    /// Public input `index` como u64: los 24 bytes altos deben ser cero
+    fn input_u64(public_inputs: &Vec<BytesN<32>>, index: u32) -> Result<u64, Error> {
+        let field = public_inputs
+            .get(index)
+            .ok_or(Error::InvalidPublicInputs)?
+            .to_array();
+        if field[..24].iter().any(|b| *b != 0) {
+            return Err(Error::InvalidPublicInputs);
+        }
+        let mut value = [0u8; 8];
+        value.copy_from_slice(&field[24..]);
+        Ok(u64::from_be_bytes(value))
+    }
+
+    /// Acuña el badge en el primer cálculo de nivel y lo actualiza en sitio después
+    fn sync_badge(env: &Env, user: &Address, level: u32) {
+        let badge_key = Self::badge_key(user);
+        match env.storage().persistent().get::<_, u32>(&badge_key) {
+            Some(token_id) => {
+                env.events()
+                    .publish((symbol_short!("badge_upd"), user.clone(), token_id), level);
+            }
+            None => {
+                let token_id: u32 = env
+                    .storage()
+                    .instance()
+                    .get(&Self::badge_seq_key())
+                    .unwrap_or(0)
+                    + 1;
+                env.storage()
+                    .instance()
+                    .set(&Self::badge_seq_key(), &token_id);
+                env.storage().persistent().set(&badge_key, &token_id);
+                env.storage()
+                    .persistent()
+                    .set(&Self::badge_owner_key(token_id), user);
+                env.events()
+                    .publish((symbol_short!("badge_mnt"), user.clone(), token_id), level);
+            }
+        }
+    }
+
+    fn level_name(level: u32) -> &'static str {
+        match level {
+            2 => "Silver",
+            3 => "Gold",
+            4 => "Platinum",
+            _ => "Bronze",
+        }
+    }
+
+    fn calculate_level(
+        goals_achieved: u32,
+        courses_completed: u32,
+        achievement_tier: u32,
+    ) -> UserLevel {
         // Platinum: 10+ metas Y 10+ cursos
         if goals_achieved >= 10 && courses_completed >= 10 {
             return UserLevel::Platinum;
         }
         
-        // Gold: 6+ metas O 6+ cursos
-        if goals_achieved >= 6 || courses_completed >= 6 {
+        // Gold: 6+ metas O 6+ cursos O logro Gold verificado
+        if goals_achieved >= 6 || courses_completed >= 6 || achievement_tier >= 3 {
             return UserLevel::Gold;
         }
         
-        // Silver: 3-5 metas O 3-5 cursos
-        if goals_achieved >= 3 || courses_completed >= 3 {
+        // Silver: 3-5 metas O 3-5 cursos O logro Silver verificado
+        if goals_achieved >= 3 || courses_completed >= 3 || achievement_tier >= 2 {
             return UserLevel::Silver;
         }
         
@@ -129,14 +755,13 @@ impl UserLevels {
 
     fn count_achieved_goals(
         env: &Env,
-        savings_goals: &Address,
+        _savings_goals: &Address,
         user: &Address,
     ) -> Result<u32, Error> {
         // En producción, esto llamaría al contrato savings-goals
         // Por ahora, retornamos un valor simulado
         // TODO: Implementar llamada real al contrato
-        use soroban_sdk::Vec;
-        let mut args = Vec::new(env);
+        let mut args: Vec<Val> = Vec::new(env);
         args.push_back(user.clone().into_val(env));
         
         // Llamar a get_savings_goal y contar cuántas están achieved
@@ -145,9 +770,9 @@ impl UserLevels {
     }
 
     fn count_completed_courses(
-        env: &Env,
-        course_completion: &Address,
-        user: &Address,
+        _env: &Env,
+        _course_completion: &Address,
+        _user: &Address,
     ) -> Result<u32, Error> {
         // En producción, esto llamaría al contrato course-completion
         // Por ahora, retornamos un valor simulado
@@ -167,26 +792,60 @@ impl UserLevels {
         (symbol_short!("level"), user.clone())
     }
 
+    fn perk_key(perk_id: u32) -> (Symbol, u32) {
+        (symbol_short!("perk"), perk_id)
+    }
+
+    fn partner_key(partner: &Address) -> (Symbol, Address) {
+        (symbol_short!("partner"), partner.clone())
+    }
+
+    fn perk_usage_key(perk_id: u32, user: &Address) -> (Symbol, u32, Address) {
+        (symbol_short!("perk_use"), perk_id, user.clone())
+    }
+
+    fn achievement_verifier_key() -> Symbol {
+        symbol_short!("ach_vrf")
+    }
+
+    fn achievement_key(achievement_id: u32) -> (Symbol, u32) {
+        (symbol_short!("ach"), achievement_id)
+    }
+
+    fn achievement_record_key(user: &Address, achievement_id: u32) -> (Symbol, Address, u32) {
+        (symbol_short!("ach_usr"), user.clone(), achievement_id)
+    }
+
+    fn achievement_best_key(user: &Address) -> (Symbol, Address) {
+        (symbol_short!("ach_best"), user.clone())
+    }
+
+    fn badge_key(user: &Address) -> (Symbol, Address) {
+        (symbol_short!("badge"), user.clone())
+    }
+
+    fn badge_owner_key(token_id: u32) -> (Symbol, u32) {
+        (symbol_short!("badge_own"), token_id)
+    }
+
+    fn badge_seq_key() -> Symbol {
+        symbol_short!("badge_seq")
+    }
+
     fn admin_key() -> Symbol {
         symbol_short!("admin")
     }
 
+    /// Exige la firma del admin guardado en `initialize`
     fn assert_admin(env: &Env) {
         let admin: Address = env
             .storage()
             .instance()
             .get(&Self::admin_key())
-            .unwrap_or_else(|| {
-                let invoker = env.invoker();
-                env.storage()
-                    .instance()
-                    .set(&Self::admin_key(), &invoker);
-                invoker
-            });
-
-        if env.invoker() != admin {
-            panic_with_error(env, &Error::NotAuthorized);
-        }
+            .unwrap_or_else(|| env.panic_with_error(Error::NotAuthorized));
+        admin.require_auth();
     }
 }
 
+mod test;
+
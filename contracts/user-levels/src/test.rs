@@ -0,0 +1,328 @@
+#![cfg(test)]
+extern crate std;
+
+use super::*;
+use soroban_sdk::{
+    contract, contracterror, contractimpl,
+    testutils::{Address as _, Ledger},
+};
+
+/// Límites por transacción de la red (CPU en instrucciones, memoria en bytes)
+const TX_CPU_LIMIT: u64 = 100_000_000;
+const TX_MEM_LIMIT: u64 = 41_943_040;
+
+fn setup(env: &Env) -> UserLevelsClient<'_> {
+    env.mock_all_auths();
+    let contract_id = env.register(UserLevels, ());
+    let client = UserLevelsClient::new(env, &contract_id);
+    client.initialize(&Address::generate(env));
+    client.set_contracts(&Address::generate(env), &Address::generate(env));
+    client
+}
+
+fn users(env: &Env, count: u32) -> Vec<Address> {
+    let mut users = Vec::new(env);
+    for _ in 0..count {
+        users.push_back(Address::generate(env));
+    }
+    users
+}
+
+#[test]
+fn batch_at_cap_fits_budget() {
+    let env = Env::default();
+    let client = setup(&env);
+    let users = users(&env, MAX_BATCH_SIZE);
+
+    // Primer cálculo: cada usuario escribe su nivel y acuña su badge (el peor caso).
+    // El contrato corre nativo en la prueba: se mide el costo del host (storage,
+    // eventos, llamadas); la ejecución del WASM se suma en la red. El host de
+    // pruebas además falla si se exceden las entradas del ledger por transacción.
+    env.cost_estimate().budget().reset_default();
+    let result = client.update_levels_batch(&users);
+
+    assert_eq!(result.processed, MAX_BATCH_SIZE);
+    assert!(result.results.iter().all(|r| r.error.is_none()));
+
+    let budget = env.cost_estimate().budget();
+    let cpu = budget.cpu_instruction_cost();
+    let mem = budget.memory_bytes_cost();
+    assert!(cpu <= TX_CPU_LIMIT, "cpu {} > {}", cpu, TX_CPU_LIMIT);
+    assert!(mem <= TX_MEM_LIMIT, "mem {} > {}", mem, TX_MEM_LIMIT);
+}
+
+#[test]
+fn batch_is_truncated_to_cap() {
+    let env = Env::default();
+    let client = setup(&env);
+    let users = users(&env, MAX_BATCH_SIZE + 5);
+
+    let result = client.update_levels_batch(&users);
+
+    assert_eq!(result.processed, MAX_BATCH_SIZE);
+    assert_eq!(result.results.len(), MAX_BATCH_SIZE);
+    assert!(client.get_user_level(&users.get_unchecked(MAX_BATCH_SIZE)).is_none());
+}
+
+#[test]
+fn redeem_perk_requires_registered_partner() {
+    let env = Env::default();
+    let client = setup(&env);
+    let partner = Address::generate(&env);
+    let other = Address::generate(&env);
+    let user = Address::generate(&env);
+    let metadata = String::from_str(&env, "10% cafetería");
+
+    assert_eq!(
+        client.try_register_perk(&1, &partner, &1, &metadata, &None, &0),
+        Err(Ok(Error::PartnerNotRegistered))
+    );
+
+    client.register_partner(&partner);
+    client.register_perk(&1, &partner, &1, &metadata, &None, &0);
+
+    // Otro socio no puede canjear el perk
+    client.register_partner(&other);
+    assert_eq!(
+        client.try_redeem_perk(&other, &user, &1),
+        Err(Ok(Error::NotAuthorized))
+    );
+
+    assert_eq!(client.redeem_perk(&partner, &user, &1), 1);
+    let signers: std::vec::Vec<Address> = env.auths().into_iter().map(|(a, _)| a).collect();
+    assert!(signers.contains(&partner) && signers.contains(&user));
+
+    // Un socio dado de baja ya no canjea
+    client.remove_partner(&partner);
+    assert_eq!(
+        client.try_redeem_perk(&partner, &user, &1),
+        Err(Ok(Error::PartnerNotRegistered))
+    );
+}
+
+#[contracterror]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MockVerifierError {
+    ProofAlreadyUsed = 5,
+    PublicInputMismatch = 9,
+}
+
+/// Verificador mínimo: acepta una sola vez cualquier proof cuyos public inputs
+/// coincidan con `expected`
+#[contract]
+pub struct MockVerifier;
+
+#[contractimpl]
+impl MockVerifier {
+    pub fn consume_proof(
+        env: Env,
+        caller: Address,
+        _circuit_id: Option<Symbol>,
+        proof_blob: Bytes,
+        _context: BytesN<32>,
+        expected: Vec<BytesN<32>>,
+        wildcards: Vec<u32>,
+    ) -> Result<VerificationResult, MockVerifierError> {
+        caller.require_auth();
+        let proof_id: BytesN<32> = env.crypto().keccak256(&proof_blob).into();
+        if env.storage().persistent().has(&proof_id) {
+            return Err(MockVerifierError::ProofAlreadyUsed);
+        }
+
+        // Blob [count][public inputs][proof], comparado como en los verificadores
+        let count = if proof_blob.len() >= 4 {
+            let mut header = [0u8; 4];
+            proof_blob.slice(0..4).copy_into_slice(&mut header);
+            u32::from_be_bytes(header)
+        } else {
+            0
+        };
+        if count != expected.len() || proof_blob.len() < 4 + count * 32 {
+            return Err(MockVerifierError::PublicInputMismatch);
+        }
+        let mut public_inputs = Vec::new(&env);
+        for i in 0..count {
+            let mut field = [0u8; 32];
+            proof_blob
+                .slice(4 + i * 32..4 + (i + 1) * 32)
+                .copy_into_slice(&mut field);
+            let input = BytesN::from_array(&env, &field);
+            if !wildcards.contains(i) && input != expected.get_unchecked(i) {
+                return Err(MockVerifierError::PublicInputMismatch);
+            }
+            public_inputs.push_back(input);
+        }
+
+        env.storage().persistent().set(&proof_id, &true);
+        Ok(VerificationResult {
+            proof_id,
+            public_inputs,
+        })
+    }
+}
+
+/// Proof blob [count][public inputs][proof] del circuito de logros:
+/// los mínimos y el tier como u64, con el hash de `user` antes del tier
+fn achievement_blob(env: &Env, user: &Address, minimums: [u64; 4], tier: u64) -> Bytes {
+    let mut blob = Bytes::from_array(env, &6u32.to_be_bytes());
+    for input in minimums {
+        blob.extend_from_array(&[0u8; 24]);
+        blob.extend_from_array(&input.to_be_bytes());
+    }
+    blob.extend_from_array(&UserLevels::user_hash(env, user).to_array());
+    blob.extend_from_array(&[0u8; 24]);
+    blob.extend_from_array(&tier.to_be_bytes());
+    blob.extend_from_array(&[7u8; 100]);
+    blob
+}
+
+#[test]
+fn achievement_proof_cannot_be_replayed_by_another_user() {
+    let env = Env::default();
+    let client = setup(&env);
+    let verifier = env.register(MockVerifier, ());
+    client.set_achievement_verifier(&verifier);
+    client.define_achievement(&1, &3, &100, &5, &80);
+
+    let user = Address::generate(&env);
+    let blob = achievement_blob(&env, &user, [6, 500, 20, 90], 2);
+    assert_eq!(client.submit_achievement(&user, &1, &blob), 2);
+    assert_eq!(client.get_achievement_tier(&user), 2);
+
+    // El blob lleva el hash de `user`: el verificador lo rechaza para otra cuenta
+    let attacker = Address::generate(&env);
+    assert_eq!(
+        client.try_submit_achievement(&attacker, &1, &blob),
+        Err(Ok(Error::ProofRejected))
+    );
+    assert_eq!(client.get_achievement_tier(&attacker), 0);
+
+    // Ya fue consumido: se rechaza con un error, sin abortar
+    assert_eq!(
+        client.try_submit_achievement(&user, &1, &blob),
+        Err(Ok(Error::ProofRejected))
+    );
+}
+
+#[test]
+fn achievement_proof_is_bound_to_the_user_hash() {
+    let env = Env::default();
+    let client = setup(&env);
+    client.set_achievement_verifier(&env.register(MockVerifier, ()));
+    client.define_achievement(&1, &3, &100, &5, &80);
+    let user = Address::generate(&env);
+    let other = Address::generate(&env);
+
+    // Un proof generado para otra cuenta no sirve, aunque nunca se haya usado
+    let foreign = achievement_blob(&env, &other, [6, 500, 20, 90], 2);
+    assert_eq!(
+        client.try_submit_achievement(&user, &1, &foreign),
+        Err(Ok(Error::ProofRejected))
+    );
+
+    // Layout anterior sin user_hash: faltan public inputs
+    let mut legacy = Bytes::from_array(&env, &5u32.to_be_bytes());
+    for input in [6u64, 500, 20, 90, 2] {
+        legacy.extend_from_array(&[0u8; 24]);
+        legacy.extend_from_array(&input.to_be_bytes());
+    }
+    legacy.extend_from_array(&[7u8; 100]);
+    assert_eq!(
+        client.try_submit_achievement(&user, &1, &legacy),
+        Err(Ok(Error::ProofRejected))
+    );
+
+    // Mínimos por debajo del catálogo
+    let weak = achievement_blob(&env, &user, [2, 500, 20, 90], 2);
+    assert_eq!(
+        client.try_submit_achievement(&user, &1, &weak),
+        Err(Ok(Error::AchievementRequirementsNotMet))
+    );
+
+    // El tier sale del output verificado
+    let no_tier = achievement_blob(&env, &user, [6, 500, 20, 90], 0);
+    assert_eq!(
+        client.try_submit_achievement(&user, &1, &no_tier),
+        Err(Ok(Error::InvalidTier))
+    );
+
+    let own = achievement_blob(&env, &user, [6, 500, 20, 90], 3);
+    assert_eq!(client.submit_achievement(&user, &1, &own), 3);
+}
+
+#[test]
+fn level_badge_exposes_token_metadata() {
+    let env = Env::default();
+    let client = setup(&env);
+
+    assert_eq!(client.name(), String::from_str(&env, "PumaPay Level"));
+    assert_eq!(client.symbol(), String::from_str(&env, "PUMALVL"));
+    assert_eq!(client.decimals(), 0);
+
+    let user = Address::generate(&env);
+    assert_eq!(client.balance(&user), 0);
+    assert!(client.badge_of(&user).is_none());
+    assert_eq!(client.try_token_uri(&1), Err(Ok(Error::BadgeNotFound)));
+}
+
+#[test]
+fn level_updates_mint_one_badge_and_update_it_in_place() {
+    let env = Env::default();
+    let client = setup(&env);
+    let verifier = env.register(MockVerifier, ());
+    client.set_achievement_verifier(&verifier);
+    client.define_achievement(&1, &3, &100, &5, &80);
+    env.ledger().set_timestamp(1_000);
+
+    // El primer cálculo de nivel acuña el badge
+    let user = Address::generate(&env);
+    assert_eq!(client.update_user_level(&user), 1);
+    assert_eq!(client.balance(&user), 1);
+    let token_id = client.badge_of(&user).unwrap();
+    assert_eq!(client.owner_of(&token_id), Some(user.clone()));
+    assert_eq!(
+        client.token_uri(&token_id),
+        String::from_str(
+            &env,
+            "{\"name\":\"PumaPay Bronze\",\"description\":\"Nivel PumaPay (soulbound)\",\"attributes\":{\"level\":\"Bronze\",\"level_value\":1,\"goals_achieved\":0,\"courses_completed\":0,\"last_updated\":1000}}"
+        )
+    );
+
+    // Subir de nivel actualiza el mismo badge en lugar de acuñar otro
+    env.ledger().set_timestamp(2_000);
+    let blob = achievement_blob(&env, &user, [6, 500, 20, 90], 3);
+    client.submit_achievement(&user, &1, &blob);
+    assert_eq!(client.get_user_level_value(&user), 3);
+    assert_eq!(client.balance(&user), 1);
+    assert_eq!(client.badge_of(&user), Some(token_id));
+    assert_eq!(
+        client.token_uri(&token_id),
+        String::from_str(
+            &env,
+            "{\"name\":\"PumaPay Gold\",\"description\":\"Nivel PumaPay (soulbound)\",\"attributes\":{\"level\":\"Gold\",\"level_value\":3,\"goals_achieved\":0,\"courses_completed\":0,\"last_updated\":2000}}"
+        )
+    );
+
+    // Otro usuario recibe el siguiente token_id, también desde el cálculo por lote
+    let other = Address::generate(&env);
+    client.update_levels_batch(&Vec::from_array(&env, [other.clone()]));
+    assert_eq!(client.badge_of(&other), Some(token_id + 1));
+    assert_eq!(client.owner_of(&(token_id + 1)), Some(other));
+}
+
+#[test]
+fn level_badge_is_soulbound() {
+    let env = Env::default();
+    let client = setup(&env);
+
+    let user = Address::generate(&env);
+    client.update_user_level(&user);
+
+    let other = Address::generate(&env);
+    assert_eq!(
+        client.try_transfer(&user, &other, &1),
+        Err(Ok(Error::SoulboundToken))
+    );
+    assert_eq!(client.balance(&user), 1);
+    assert_eq!(client.balance(&other), 0);
+}
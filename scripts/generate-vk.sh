@@ -6,11 +6,11 @@
 set -e
 
 CIRCUIT_DIR="${1:-circuits/savings-proof}"
-OUTPUT_FILE="${2:-circuits/savings-proof/vk.json}"
+OUTPUT_PATH="${2:-$CIRCUIT_DIR/target}"
 
 echo "🔧 Generando Verification Key (VK) para el circuito..."
 echo "📁 Directorio del circuito: $CIRCUIT_DIR"
-echo "📄 Archivo de salida: $OUTPUT_FILE"
+echo "📄 Salida: $OUTPUT_PATH"
 
 # Verificar que bb está instalado
 if ! command -v bb &> /dev/null; then
@@ -36,20 +36,26 @@ echo "📦 Archivo ACIR encontrado: $ACIR_FILE"
 
 # Generar el VK usando bb
 echo "🔄 Generando VK con bb..."
-bb write_vk -b "$ACIR_FILE" -o "$OUTPUT_FILE"
+bb write_vk -b "$ACIR_FILE" -o "$OUTPUT_PATH"
 
-if [ $? -eq 0 ]; then
-    echo "✅ VK generado exitosamente: $OUTPUT_FILE"
+# bb recientes escriben <dir>/vk; versiones anteriores escriben directo al archivo
+VK_FILE="$OUTPUT_PATH"
+if [ -d "$OUTPUT_PATH" ]; then
+    VK_FILE="$OUTPUT_PATH/vk"
+fi
+
+if [ -f "$VK_FILE" ]; then
+    echo "✅ VK generado exitosamente: $VK_FILE"
     echo ""
     echo "📋 Próximos pasos:"
-    echo "1. Lee el contenido de $OUTPUT_FILE"
-    echo "2. Configura el VK en el verificador simple:"
+    echo "Configura el VK binario en el verificador Ultrahonk (no hace falta convertirlo a JSON):"
     echo "   stellar contract invoke \\"
-    echo "     --id CAE5SCP7O6CEC4HQZKSODMULY5VLLDQTYNNXX46L47CXW72B3FMAKJLT \\"
+    echo "     --id CONTRACT_ID_ULTRAHONK \\"
     echo "     --source-account issuer \\"
     echo "     --network testnet \\"
-    echo "     -- set_vk \\"
-    echo "     --vk_json <contenido-del-archivo-vk.json>"
+    echo "     -- set_vk_binary \\"
+    echo "     --caller ADMIN_PUBLIC_KEY \\"
+    echo "     --vk $(xxd -p "$VK_FILE" | tr -d '\n')"
 else
     echo "❌ Error generando VK"
     exit 1